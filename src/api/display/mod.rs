@@ -1,6 +1,7 @@
 pub mod preview;
 
 use crate::api::{AppError, AppState};
+use crate::context::device::DeviceTelemetry;
 use crate::context::load_contexts;
 use crate::display::generate_filename;
 use crate::dto::{ApiDisplayResponse, SpecialFunction};
@@ -68,6 +69,16 @@ pub async fn display_handler(
     let device_config = app_state.get_device_config_by_api_key(&headers.api_key)?;
     let base_url = app_state.config()?.base_url;
     let now = app_state.clock.now();
+    app_state.record_telemetry(
+        &device_config.friendly_id,
+        DeviceTelemetry {
+            battery_voltage: headers.battery_voltage.trim().parse().ok(),
+            rssi: headers.rssi.trim().parse().ok(),
+            fw_version: Some(headers.fw_version.clone()).filter(|v| !v.is_empty()),
+            refresh_rate: headers.refresh_rate.trim().parse().ok(),
+            last_seen: now,
+        },
+    );
 
     let filename = generate_filename(headers.api_key, now)?;
     let mut image_url = Url::parse(&base_url).context(format!("invalid base url, {}", base_url))?;
@@ -135,18 +146,32 @@ pub async fn image_handler(
     let device_config = app_state.get_device_config_by_friendly_id(friendly_id)?;
     let playlist_item = device_config.get_next(timestamp);
     let display_renderer = app_state.display_renderer()?;
-    let context = load_contexts(app_state, friendly_id, playlist_item.contexts.clone()).await?;
-
-    let mut result = Map::new();
-    for (k, v) in context.iter() {
-        let value = serde_json::to_string(v)
-            .context(format!("failed to serialize context value for key {}", k))?;
-        let value = serde_json::from_str(value.as_str())
-            .context(format!("failed to deserialize context value for key {}", k))?;
-        result.insert(k.clone(), value);
-    }
-
-    let image = display_renderer.render_jinja(&playlist_item.filename, &result)?;
+    let image = match &playlist_item.mashup {
+        Some(mashup) => {
+            let mut regions = vec![];
+            for region in &mashup.regions {
+                let context =
+                    load_contexts(app_state.clone(), friendly_id, region.contexts.clone()).await?;
+                regions.push((region.cell.clone(), region.filename.clone(), context));
+            }
+            display_renderer.render_mashup(&mashup.layout, &regions)?
+        }
+        None => {
+            let context =
+                load_contexts(app_state, friendly_id, playlist_item.contexts.clone()).await?;
+
+            let mut result = Map::new();
+            for (k, v) in context.iter() {
+                let value = serde_json::to_string(v)
+                    .context(format!("failed to serialize context value for key {}", k))?;
+                let value = serde_json::from_str(value.as_str())
+                    .context(format!("failed to deserialize context value for key {}", k))?;
+                result.insert(k.clone(), value);
+            }
+
+            display_renderer.render_file(&playlist_item.filename, &result)?
+        }
+    };
     let image = image.to_vec();
     let mut res = Body::from(image).into_response();
     res.headers_mut()
@@ -3,8 +3,10 @@ use crate::api::display::preview::{
 };
 use crate::api::display::{display_handler, image_handler};
 use crate::api::setup::{setup_handler, setup_image_handler};
-use crate::context::ContextConfig;
+use crate::context::device::DeviceTelemetry;
+use crate::context::{ContextConfig, ContextRegistry};
 use crate::display::DisplayRenderer;
+use crate::display::mashup::{MashupCell, MashupLayout};
 use anyhow::{Context, Error, Result};
 use axum::Router;
 use axum::http::StatusCode;
@@ -12,9 +14,10 @@ use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use config::{Config, Map, Value};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use tower::ServiceBuilder;
 use tower_http::services::ServeDir;
@@ -49,8 +52,26 @@ pub struct AppDeviceConfig {
 
 #[derive(Clone, Deserialize)]
 pub struct AppPlaylistItem {
+    #[serde(default)]
     pub filename: String,
+    #[serde(default)]
     pub contexts: Vec<String>,
+    pub mashup: Option<AppMashupConfig>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct AppMashupConfig {
+    pub layout: MashupLayout,
+    pub regions: Vec<AppMashupRegion>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct AppMashupRegion {
+    pub filename: String,
+    #[serde(default)]
+    pub contexts: Vec<String>,
+    #[serde(flatten)]
+    pub cell: MashupCell,
 }
 
 impl AppDeviceConfig {
@@ -65,10 +86,13 @@ pub struct AppConfig {
     pub devices: Option<Vec<AppDeviceConfig>>,
     pub base_url: String,
     pub setup_image_path: String,
+    pub setup_template: Option<String>,
     pub display_image_timeout: u64,
     pub templates_path: PathBuf,
     pub fonts_path: PathBuf,
+    pub assets_path: Option<PathBuf>,
     pub default_context_path: PathBuf,
+    #[serde(default)]
     pub default_context: Map<String, Value>,
 }
 
@@ -99,6 +123,9 @@ impl AppConfig {
 pub struct AppState {
     pub server_config: AppServerConfig,
     pub clock: Arc<dyn Clock + Sync + Send>,
+    pub context_registry: Arc<ContextRegistry>,
+    /// The latest telemetry reported by each device, keyed by friendly_id.
+    pub telemetry: Arc<Mutex<HashMap<String, DeviceTelemetry>>>,
 }
 
 impl AppState {
@@ -121,10 +148,12 @@ impl AppState {
     ) -> Result<T> {
         let name = T::name();
         let config = self.config()?;
-        let default_context_config = config
+        // Contexts whose config has only defaults don't need a `[default_context.*]` table
+        let default_context_config = &config
             .default_context
             .get(name)
-            .context(format!("Failed to get default context config {}", name))?;
+            .cloned()
+            .unwrap_or_else(|| Value::from(Map::<String, Value>::new()));
         let device_context_config = match self
             .get_device_config_by_friendly_id(friendly_id)
             .context(format!("failed to device config {}", friendly_id))?
@@ -154,11 +183,22 @@ impl AppState {
         Ok(Value::from(context_config).try_deserialize()?)
     }
 
+    pub fn record_telemetry(&self, friendly_id: &str, telemetry: DeviceTelemetry) {
+        if let Ok(mut devices) = self.telemetry.lock() {
+            devices.insert(friendly_id.to_string(), telemetry);
+        }
+    }
+
+    pub fn get_telemetry(&self, friendly_id: &str) -> Option<DeviceTelemetry> {
+        self.telemetry.lock().ok()?.get(friendly_id).cloned()
+    }
+
     pub fn display_renderer(&self) -> Result<DisplayRenderer> {
         let config = self.config()?;
         Ok(DisplayRenderer::new(
             config.fonts_path,
             config.templates_path,
+            config.assets_path,
         )?)
     }
 
@@ -235,6 +275,8 @@ pub fn app(server_config: AppServerConfig, clock: Arc<dyn Clock + Sync + Send>)
     let state = AppState {
         server_config,
         clock,
+        context_registry: Arc::new(ContextRegistry::builtin()),
+        telemetry: Arc::default(),
     };
 
     let fonts_path = state.config()?.fonts_path;
@@ -317,6 +359,24 @@ mod test {
             [[devices.playlist]]
             filename = "test.svg.jinja"
             contexts = [ ]
+
+            [[devices]]
+            mac_address = "fake_mac_address_mashup"
+            friendly_id = "fake_friendly_id_mashup"
+            api_key = "fake_api_key_mashup"
+            setup_expiry = "9999-01-01T00:00:00Z"
+
+            [[devices.playlist]]
+            mashup.layout = {{ grid = {{ columns = 3, rows = 1 }} }}
+
+            [[devices.playlist.mashup.regions]]
+            filename = "test.svg.jinja"
+            column_span = 2
+
+            [[devices.playlist.mashup.regions]]
+            filename = "test.svg.jinja"
+            column = 2
+            contexts = [ ]
         "#
         )
         .expect("Failed to write config");
@@ -452,4 +512,32 @@ mod test {
         let expected = fs::read("test.bmp").unwrap();
         assert_eq!(expected, response.as_bytes().iter().as_ref())
     }
+
+    #[tokio::test]
+    async fn it_should_return_mashup_display_response() {
+        let (app, _temp_files) = new_test_app();
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1234567890);
+        let filename =
+            crate::display::generate_filename("fake_api_key_mashup".to_string(), timestamp)
+                .unwrap();
+
+        let response = app
+            .get(&format!("/display/{}", filename))
+            .add_query_param("friendly-id", "fake_friendly_id_mashup")
+            .add_query_param("timestamp", "1234567890")
+            .await;
+        let image = response.as_bytes();
+        assert_eq!(image.len(), 48062);
+        // The 800x480 template is scaled to fit each region and centered vertically, leaving
+        // white bands above and below it.
+        let any_black = |columns, rows| crate::display::tests::any_black(image, columns, rows);
+        // The first region spans two of three columns, 533x480 with content 320 high.
+        assert!(!any_black(0..533, 0..78));
+        assert!(any_black(0..533, 82..398));
+        assert!(!any_black(0..533, 402..480));
+        // The second region is 267x480 with content 160 high.
+        assert!(!any_black(535..800, 0..158));
+        assert!(any_black(535..800, 162..318));
+        assert!(!any_black(535..800, 322..480));
+    }
 }
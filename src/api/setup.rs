@@ -8,19 +8,33 @@ use axum::extract::State;
 use axum::http::{HeaderMap, HeaderValue, header};
 use axum::response::{IntoResponse, Response};
 use chrono::{DateTime, Local};
+use serde_json::{Map, json};
 use std::fs::File;
 use std::io::Read;
 use tracing::info;
 
 pub async fn setup_image_handler(State(app_state): State<AppState>) -> Result<Response, AppError> {
     let config = app_state.config()?;
-    let setup_image_path = config.setup_image_path.clone();
-    let mut file = File::open(setup_image_path)
-        .context(config.setup_image_path.clone())
-        .expect("Failed to open setup image");
-    let mut image = vec![];
-    file.read_to_end(&mut image)
-        .expect("Failed to read setup image");
+    let image = match &config.setup_template {
+        Some(setup_template) => {
+            let mut ctx = Map::new();
+            ctx.insert("setup".to_string(), json!({ "base_url": config.base_url }));
+            app_state
+                .display_renderer()?
+                .render_file(setup_template, &ctx)?
+                .to_vec()
+        }
+        None => {
+            let setup_image_path = config.setup_image_path.clone();
+            let mut file = File::open(setup_image_path)
+                .context(config.setup_image_path.clone())
+                .expect("Failed to open setup image");
+            let mut image = vec![];
+            file.read_to_end(&mut image)
+                .expect("Failed to read setup image");
+            image
+        }
+    };
 
     let mut res = Body::from(image).into_response();
     res.headers_mut()
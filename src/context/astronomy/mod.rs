@@ -0,0 +1,407 @@
+use crate::api::AppState;
+use crate::context::time::{FormatTime, parse_timezone};
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::Context;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const SUNRISE_ZENITH: f64 = 90.833;
+const CIVIL_TWILIGHT_ZENITH: f64 = 96.0;
+const SYNODIC_MONTH_DAYS: f64 = 29.530588853;
+const MOON_PHASES: [&str; 8] = [
+    "new_moon",
+    "waxing_crescent",
+    "first_quarter",
+    "waxing_gibbous",
+    "full_moon",
+    "waning_gibbous",
+    "last_quarter",
+    "waning_crescent",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct AppAstronomyConfig {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+}
+
+fn default_time_format() -> String {
+    "%H:%M".to_string()
+}
+
+impl ContextConfig for AppAstronomyConfig {
+    fn name() -> &'static str {
+        "astronomy"
+    }
+}
+
+/// Times are `None` when the sun doesn't cross the horizon, or twilight angle, on that day.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AstronomyContext {
+    pub date: String,
+    pub sunrise: Option<String>,
+    pub sunset: Option<String>,
+    pub solar_noon: String,
+    pub civil_dawn: Option<String>,
+    pub civil_dusk: Option<String>,
+    pub day_length: String,
+    pub day_length_minutes: i64,
+    pub is_day: bool,
+    pub moon: MoonPhase,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MoonPhase {
+    pub phase: &'static str,
+    /// 0 at new moon, 0.5 at full moon.
+    pub phase_fraction: f64,
+    pub illumination: f64,
+    pub age_days: f64,
+}
+
+pub struct AstronomyContextProvider;
+
+impl ContextProvider for AstronomyContextProvider {
+    type Config = AppAstronomyConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        _friendly_id: &str,
+        config: AppAstronomyConfig,
+    ) -> anyhow::Result<Value> {
+        let tz = parse_timezone(&config.timezone)?;
+        let now = DateTime::<Utc>::from(app_state.clock.now()).with_timezone(&tz);
+        Ok(serde_json::to_value(create_astronomy_context(
+            now, &config,
+        )?)?)
+    }
+}
+
+fn create_astronomy_context(
+    now: DateTime<Tz>,
+    config: &AppAstronomyConfig,
+) -> anyhow::Result<AstronomyContext> {
+    let date = now.date_naive();
+    let (latitude, longitude) = (config.latitude, config.longitude);
+    let format = |time: DateTime<Utc>| {
+        time.with_timezone(&now.timezone())
+            .format_time(&config.time_format)
+    };
+    let sunrise = solar_event(date, latitude, longitude, SUNRISE_ZENITH, true)?;
+    let sunset = solar_event(date, latitude, longitude, SUNRISE_ZENITH, false)?;
+    let civil_dawn = solar_event(date, latitude, longitude, CIVIL_TWILIGHT_ZENITH, true)?;
+    let civil_dusk = solar_event(date, latitude, longitude, CIVIL_TWILIGHT_ZENITH, false)?;
+    let solar_noon = solar_noon(date, longitude)?;
+
+    let now_utc = now.with_timezone(&Utc);
+    let (day_length_minutes, is_day) = match (sunrise, sunset) {
+        (Event::At(sunrise), Event::At(sunset)) => (
+            (sunset - sunrise).num_minutes(),
+            sunrise <= now_utc && now_utc < sunset,
+        ),
+        (Event::AlwaysAbove, _) | (_, Event::AlwaysAbove) => (24 * 60, true),
+        _ => (0, false),
+    };
+
+    Ok(AstronomyContext {
+        date: date.format("%Y-%m-%d").to_string(),
+        sunrise: sunrise.time().map(format).transpose()?,
+        sunset: sunset.time().map(format).transpose()?,
+        solar_noon: format(solar_noon)?,
+        civil_dawn: civil_dawn.time().map(format).transpose()?,
+        civil_dusk: civil_dusk.time().map(format).transpose()?,
+        day_length: format!("{}:{:02}", day_length_minutes / 60, day_length_minutes % 60),
+        day_length_minutes,
+        is_day,
+        moon: moon_phase(now_utc),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Event {
+    At(DateTime<Utc>),
+    AlwaysAbove,
+    AlwaysBelow,
+}
+
+impl Event {
+    fn time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Event::At(time) => Some(*time),
+            _ => None,
+        }
+    }
+}
+
+fn julian_day(time: DateTime<Utc>) -> f64 {
+    time.timestamp() as f64 / 86400.0 + 2440587.5
+}
+
+fn utc_midnight(date: NaiveDate) -> anyhow::Result<DateTime<Utc>> {
+    Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).context("Invalid date")?))
+}
+
+/// Declination in degrees and the equation of time in minutes, from the NOAA solar calculator.
+fn sun_position(julian_day: f64) -> (f64, f64) {
+    let t = (julian_day - 2451545.0) / 36525.0;
+    let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+    let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+    let center = mean_anomaly.to_radians().sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + (2.0 * mean_anomaly).to_radians().sin() * (0.019993 - 0.000101 * t)
+        + (3.0 * mean_anomaly).to_radians().sin() * 0.000289;
+    let omega = 125.04 - 1934.136 * t;
+    let apparent_longitude = mean_longitude + center - 0.00569 - 0.00478 * omega.to_radians().sin();
+    let mean_obliquity =
+        23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = mean_obliquity + 0.00256 * omega.to_radians().cos();
+    let declination = (obliquity.to_radians().sin() * apparent_longitude.to_radians().sin())
+        .asin()
+        .to_degrees();
+
+    let y = (obliquity.to_radians() / 2.0).tan().powi(2);
+    let (l0, m) = (mean_longitude.to_radians(), mean_anomaly.to_radians());
+    let equation_of_time = 4.0
+        * (y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+            + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+            - 0.5 * y * y * (4.0 * l0).sin()
+            - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+        .to_degrees();
+    (declination, equation_of_time)
+}
+
+fn solar_noon(date: NaiveDate, longitude: f64) -> anyhow::Result<DateTime<Utc>> {
+    let midnight = utc_midnight(date)?;
+    let mut minutes = 720.0 - 4.0 * longitude;
+    for _ in 0..2 {
+        let (_, equation_of_time) = sun_position(julian_day(midnight) + minutes / 1440.0);
+        minutes = 720.0 - 4.0 * longitude - equation_of_time;
+    }
+    Ok(midnight + Duration::seconds((minutes * 60.0).round() as i64))
+}
+
+/// The time the sun's center crosses `zenith` degrees, refined once at the estimated time.
+fn solar_event(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+    zenith: f64,
+    rising: bool,
+) -> anyhow::Result<Event> {
+    let midnight = utc_midnight(date)?;
+    let mut minutes = 720.0 - 4.0 * longitude;
+    for _ in 0..2 {
+        let (declination, equation_of_time) = sun_position(julian_day(midnight) + minutes / 1440.0);
+        let (latitude, declination) = (latitude.to_radians(), declination.to_radians());
+        let cos_hour_angle = zenith.to_radians().cos() / (latitude.cos() * declination.cos())
+            - latitude.tan() * declination.tan();
+        if cos_hour_angle < -1.0 {
+            return Ok(Event::AlwaysAbove);
+        }
+        if cos_hour_angle > 1.0 {
+            return Ok(Event::AlwaysBelow);
+        }
+        let hour_angle = cos_hour_angle.acos().to_degrees();
+        let noon = 720.0 - 4.0 * longitude - equation_of_time;
+        minutes = if rising {
+            noon - 4.0 * hour_angle
+        } else {
+            noon + 4.0 * hour_angle
+        };
+    }
+    Ok(Event::At(
+        midnight + Duration::seconds((minutes * 60.0).round() as i64),
+    ))
+}
+
+/// Sunrise and sunset, `None` during polar day or night.
+pub(crate) type SunriseSunset = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+pub(crate) fn sunrise_sunset(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> anyhow::Result<SunriseSunset> {
+    Ok((
+        solar_event(date, latitude, longitude, SUNRISE_ZENITH, true)?.time(),
+        solar_event(date, latitude, longitude, SUNRISE_ZENITH, false)?.time(),
+    ))
+}
+
+/// Whether the sun is above the horizon, allowing for refraction like sunrise and sunset.
+pub(crate) fn is_sun_up(time: DateTime<Utc>, latitude: f64, longitude: f64) -> bool {
+    let (declination, equation_of_time) = sun_position(julian_day(time));
+    let minutes = time.timestamp().rem_euclid(86400) as f64 / 60.0;
+    let hour_angle = ((minutes + equation_of_time + 4.0 * longitude) / 4.0 - 180.0).to_radians();
+    let (latitude, declination) = (latitude.to_radians(), declination.to_radians());
+    let cos_zenith =
+        latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+    cos_zenith > SUNRISE_ZENITH.to_radians().cos()
+}
+
+/// Phase angle from the low precision formulas in Meeus, Astronomical Algorithms, chapter 48.
+fn moon_phase(time: DateTime<Utc>) -> MoonPhase {
+    let t = (julian_day(time) - 2451545.0) / 36525.0;
+    let elongation = (297.8501921 + 445267.1114034 * t).to_radians();
+    let sun_anomaly = (357.5291092 + 35999.0502909 * t).to_radians();
+    let moon_anomaly = (134.9633964 + 477198.8675055 * t).to_radians();
+    let phase_angle = 180.0 - elongation.to_degrees() - 6.289 * moon_anomaly.sin()
+        + 2.100 * sun_anomaly.sin()
+        - 1.274 * (2.0 * elongation - moon_anomaly).sin()
+        - 0.658 * (2.0 * elongation).sin()
+        - 0.214 * (2.0 * moon_anomaly).sin()
+        - 0.110 * elongation.sin();
+    let illumination = (1.0 + phase_angle.to_radians().cos()) / 2.0;
+    let phase_fraction = (180.0 - phase_angle).rem_euclid(360.0) / 360.0;
+    let index = (phase_fraction * 8.0).round() as usize % MOON_PHASES.len();
+    MoonPhase {
+        phase: MOON_PHASES[index],
+        phase_fraction: round(phase_fraction, 3),
+        illumination: round(illumination, 3),
+        age_days: round(phase_fraction * SYNODIC_MONTH_DAYS, 1),
+    }
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use serde_json::json;
+
+    fn portland() -> AppAstronomyConfig {
+        AppAstronomyConfig {
+            latitude: 45.528744,
+            longitude: -122.696236,
+            timezone: "America/Los_Angeles".to_string(),
+            time_format: "%H:%M".to_string(),
+        }
+    }
+
+    fn at(time: &str, timezone: &str) -> DateTime<Tz> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&timezone.parse::<Tz>().unwrap())
+    }
+
+    #[test]
+    fn it_should_compute_sun_times() {
+        let context = create_astronomy_context(
+            at("2025-06-21T12:00:00-07:00", "America/Los_Angeles"),
+            &portland(),
+        )
+        .unwrap();
+        assert_eq!(context.sunrise.as_deref(), Some("05:22"));
+        assert_eq!(context.sunset.as_deref(), Some("21:03"));
+        assert_eq!(context.civil_dawn.as_deref(), Some("04:43"));
+        assert_eq!(context.civil_dusk.as_deref(), Some("21:41"));
+        assert_eq!(context.solar_noon, "13:12");
+        assert_eq!(context.day_length, "15:41");
+        assert!(context.is_day);
+
+        let winter = create_astronomy_context(
+            at("2025-12-21T20:00:00-08:00", "America/Los_Angeles"),
+            &portland(),
+        )
+        .unwrap();
+        assert_eq!(winter.sunrise.as_deref(), Some("07:48"));
+        assert_eq!(winter.sunset.as_deref(), Some("16:30"));
+        assert!(!winter.is_day);
+    }
+
+    #[test]
+    fn it_should_reject_invalid_time_format() {
+        let config = AppAstronomyConfig {
+            time_format: "%Q".to_string(),
+            ..portland()
+        };
+        let error = create_astronomy_context(
+            at("2025-06-21T12:00:00-07:00", "America/Los_Angeles"),
+            &config,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid time format %Q");
+    }
+
+    #[test]
+    fn it_should_handle_polar_day_and_night() {
+        let tromso = AppAstronomyConfig {
+            latitude: 69.6492,
+            longitude: 18.9553,
+            timezone: "Europe/Oslo".to_string(),
+            time_format: "%H:%M".to_string(),
+        };
+        let summer =
+            create_astronomy_context(at("2025-06-21T12:00:00+02:00", "Europe/Oslo"), &tromso)
+                .unwrap();
+        assert_eq!(summer.sunrise, None);
+        assert_eq!(summer.day_length, "24:00");
+        assert!(summer.is_day);
+
+        let winter =
+            create_astronomy_context(at("2025-12-21T12:00:00+01:00", "Europe/Oslo"), &tromso)
+                .unwrap();
+        assert_eq!(winter.sunset, None);
+        assert_eq!(winter.day_length_minutes, 0);
+        assert!(winter.civil_dawn.is_some());
+    }
+
+    #[test]
+    fn it_should_tell_whether_the_sun_is_up() {
+        let (latitude, longitude) = (45.528744, -122.696236);
+        let up = |time: &str| is_sun_up(time.parse().unwrap(), latitude, longitude);
+        // Sunrise is 05:22 and sunset 21:03 in Portland on 2025-06-21.
+        assert!(!up("2025-06-21T05:20:00-07:00"));
+        assert!(up("2025-06-21T05:25:00-07:00"));
+        assert!(up("2025-06-21T21:00:00-07:00"));
+        assert!(!up("2025-06-21T21:06:00-07:00"));
+    }
+
+    #[test]
+    fn it_should_compute_moon_phase() {
+        let full = moon_phase("2025-06-11T07:44:00Z".parse().unwrap());
+        assert_eq!(full.phase, "full_moon");
+        assert!(full.illumination > 0.99);
+        let new = moon_phase("2025-06-25T10:31:00Z".parse().unwrap());
+        assert_eq!(new.phase, "new_moon");
+        assert!(new.illumination < 0.01);
+        let first_quarter = moon_phase("2025-06-03T03:41:00Z".parse().unwrap());
+        assert_eq!(first_quarter.phase, "first_quarter");
+        assert!((first_quarter.illumination - 0.5).abs() < 0.02);
+        assert!((first_quarter.age_days - 7.4).abs() < 0.5);
+    }
+
+    #[tokio::test]
+    async fn it_should_load_astronomy_context() {
+        let mut registry = ContextRegistry::default();
+        registry.register(AstronomyContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            r#"
+            [default_context.astronomy]
+            latitude = 51.5072
+            longitude = -0.1276
+            timezone = "Europe/London"
+            "#,
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["astronomy".to_string()])
+            .await
+            .unwrap();
+        // The test clock is 2009-02-13 23:31 in London.
+        assert_eq!(contexts["astronomy"]["date"], json!("2009-02-13"));
+        assert_eq!(contexts["astronomy"]["sunrise"], json!("07:18"));
+        assert_eq!(contexts["astronomy"]["is_day"], json!(false));
+    }
+}
@@ -0,0 +1,659 @@
+use crate::api::AppState;
+use crate::context::time::{FormatTime, parse_timezone};
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use ical::parser::ical::component::{IcalCalendar, IcalEvent};
+use ical::property::Property;
+use rrule::{RRule, RRuleSet, Unvalidated};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::BufReader;
+use tracing::{info, warn};
+
+const MAX_OCCURRENCES: u16 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct AppCalendarConfig {
+    /// Paths to `.ics` files or `http(s)://` and `webcal://` URLs.
+    pub sources: Vec<String>,
+    pub timezone: String,
+    #[serde(default = "default_days")]
+    pub days: u32,
+    #[serde(default = "default_max_events")]
+    pub max_events: usize,
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+}
+
+fn default_days() -> u32 {
+    7
+}
+
+fn default_max_events() -> usize {
+    10
+}
+
+fn default_time_format() -> String {
+    "%H:%M".to_string()
+}
+
+impl ContextConfig for AppCalendarConfig {
+    fn name() -> &'static str {
+        "calendar"
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CalendarContext {
+    pub today: Vec<CalendarEvent>,
+    pub upcoming: Vec<CalendarEvent>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CalendarEvent {
+    pub title: String,
+    pub location: Option<String>,
+    pub all_day: bool,
+    pub start: String,
+    pub end: String,
+    pub date: String,
+    pub weekday: String,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+pub struct CalendarContextProvider;
+
+impl ContextProvider for CalendarContextProvider {
+    type Config = AppCalendarConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        _friendly_id: &str,
+        config: AppCalendarConfig,
+    ) -> anyhow::Result<Value> {
+        info!("Getting calendar data with config {:?}", config);
+        let mut calendars = vec![];
+        for source in &config.sources {
+            let data = read_source(source).await?;
+            calendars
+                .extend(parse_calendars(&data).context(format!("Failed to parse {}", source))?);
+        }
+        let tz = parse_timezone(&config.timezone)?;
+        let now = DateTime::<Utc>::from(app_state.clock.now()).with_timezone(&tz);
+        Ok(serde_json::to_value(create_agenda(
+            &calendars, now, &config,
+        )?)?)
+    }
+}
+
+async fn read_source(source: &str) -> anyhow::Result<String> {
+    let url = match source.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => source.to_string(),
+    };
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let result = reqwest::get(&url)
+            .await
+            .context(format!("Failed to request {}", url))?
+            .error_for_status()?
+            .text()
+            .await?;
+        return Ok(result);
+    }
+    std::fs::read_to_string(source).context(format!("Failed to read {}", source))
+}
+
+fn parse_calendars(data: &str) -> anyhow::Result<Vec<IcalCalendar>> {
+    let mut calendars = vec![];
+    for calendar in ical::IcalParser::new(BufReader::new(data.as_bytes())) {
+        calendars.push(calendar?);
+    }
+    Ok(calendars)
+}
+
+struct Occurrence {
+    title: String,
+    location: Option<String>,
+    all_day: bool,
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+}
+
+impl Occurrence {
+    fn to_event(&self, time_format: &str) -> anyhow::Result<CalendarEvent> {
+        Ok(CalendarEvent {
+            title: self.title.clone(),
+            location: self.location.clone(),
+            all_day: self.all_day,
+            start: self.start.to_rfc3339(),
+            end: self.end.to_rfc3339(),
+            date: self.start.format("%Y-%m-%d").to_string(),
+            weekday: self.start.format("%A").to_string(),
+            start_time: self.start.format_time(time_format)?,
+            end_time: self.end.format_time(time_format)?,
+        })
+    }
+}
+
+/// Today holds every event overlapping the current day, upcoming holds the events starting
+/// after today within the configured number of days.
+fn create_agenda(
+    calendars: &[IcalCalendar],
+    now: DateTime<Tz>,
+    config: &AppCalendarConfig,
+) -> anyhow::Result<CalendarContext> {
+    let tz = now.timezone();
+    let today = local_midnight(tz, now.date_naive())?;
+    let tomorrow = local_midnight(tz, now.date_naive() + Duration::days(1))?;
+    let until = local_midnight(tz, now.date_naive() + Duration::days(config.days as i64))?;
+
+    let events: Vec<&IcalEvent> = calendars
+        .iter()
+        .flat_map(|calendar| &calendar.events)
+        .collect();
+    // A cancelled override still removes the occurrence it replaces
+    let mut overridden = HashSet::new();
+    for event in &events {
+        if let (Some(uid), Some(recurrence_id)) =
+            (text(event, "UID"), property(event, "RECURRENCE-ID"))
+        {
+            overridden.insert((
+                uid,
+                start_of(parse_time(recurrence_id, tz)?, tz)?.timestamp(),
+            ));
+        }
+    }
+
+    let mut occurrences = vec![];
+    for event in events {
+        if text(event, "STATUS").as_deref() == Some("CANCELLED") {
+            continue;
+        }
+        occurrences.extend(
+            expand_event(event, tz, today, until, &overridden).context(format!(
+                "Failed to expand event {:?}",
+                text(event, "SUMMARY")
+            ))?,
+        );
+    }
+    occurrences
+        .sort_by(|a, b| (a.start, !a.all_day, &a.title).cmp(&(b.start, !b.all_day, &b.title)));
+
+    let (today, upcoming): (Vec<_>, Vec<_>) = occurrences
+        .into_iter()
+        .partition(|occurrence| occurrence.start < tomorrow);
+    Ok(CalendarContext {
+        today: today
+            .iter()
+            .map(|occurrence| occurrence.to_event(&config.time_format))
+            .collect::<anyhow::Result<_>>()?,
+        upcoming: upcoming
+            .iter()
+            .take(config.max_events)
+            .map(|occurrence| occurrence.to_event(&config.time_format))
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
+
+fn expand_event(
+    event: &IcalEvent,
+    tz: Tz,
+    from: DateTime<Tz>,
+    to: DateTime<Tz>,
+    overridden: &HashSet<(String, i64)>,
+) -> anyhow::Result<Vec<Occurrence>> {
+    let dtstart = parse_time(property(event, "DTSTART").context("Missing DTSTART")?, tz)?;
+    let all_day = matches!(dtstart, IcsTime::Date(_));
+    let start = start_of(dtstart, tz)?;
+    let end = match (property(event, "DTEND"), text(event, "DURATION")) {
+        (Some(dtend), _) => start_of(parse_time(dtend, tz)?, tz)?,
+        (None, Some(duration)) => start + parse_duration(&duration)?,
+        (None, None) if all_day => start + Duration::days(1),
+        (None, None) => start,
+    };
+    let duration = end - start;
+    let all_day_days = (end.date_naive() - start.date_naive()).num_days().max(1);
+
+    let starts = match text(event, "RRULE") {
+        Some(rule) if property(event, "RECURRENCE-ID").is_none() => {
+            match expand_rule(&rule, start, from - duration, to) {
+                Ok(starts) => starts,
+                Err(e) => {
+                    warn!("Ignoring invalid RRULE {}: {:#}", rule, e);
+                    vec![start]
+                }
+            }
+        }
+        _ => vec![start],
+    };
+
+    let uid = text(event, "UID").unwrap_or_default();
+    let mut excluded = HashSet::new();
+    for exdate in event.properties.iter().filter(|p| p.name == "EXDATE") {
+        for value in exdate.value.iter().flat_map(|value| value.split(',')) {
+            let time = parse_time_value(value, param(exdate, "TZID"), tz)?;
+            excluded.insert(start_of(time, tz)?.timestamp());
+        }
+    }
+    let is_override = property(event, "RECURRENCE-ID").is_some();
+
+    let mut occurrences = vec![];
+    for start in starts {
+        let timestamp = start.timestamp();
+        if excluded.contains(&timestamp)
+            || (!is_override && overridden.contains(&(uid.clone(), timestamp)))
+        {
+            continue;
+        }
+        let (start, end) = if all_day {
+            let date = start.with_timezone(&tz).date_naive();
+            (
+                local_midnight(tz, date)?,
+                local_midnight(tz, date + Duration::days(all_day_days))?,
+            )
+        } else {
+            (
+                start.with_timezone(&tz),
+                (start + duration).with_timezone(&tz),
+            )
+        };
+        if start >= to || (end <= from && start < from) {
+            continue;
+        }
+        occurrences.push(Occurrence {
+            title: text(event, "SUMMARY").unwrap_or_default(),
+            location: text(event, "LOCATION").filter(|location| !location.is_empty()),
+            all_day,
+            start,
+            end,
+        });
+    }
+    Ok(occurrences)
+}
+
+/// Expands the rule in the timezone of the first occurrence so local times survive DST changes.
+fn expand_rule(
+    rule: &str,
+    start: DateTime<Tz>,
+    after: DateTime<Tz>,
+    before: DateTime<Tz>,
+) -> anyhow::Result<Vec<DateTime<Tz>>> {
+    let rrule_tz = rrule::Tz::Tz(start.timezone());
+    let dt_start = start.with_timezone(&rrule_tz);
+    let rule = normalize_until(rule, start.timezone())?
+        .parse::<RRule<Unvalidated>>()?
+        .validate(dt_start)?;
+    let result = RRuleSet::new(dt_start)
+        .rrule(rule)
+        .after(after.with_timezone(&rrule_tz))
+        .before(before.with_timezone(&rrule_tz))
+        .all(MAX_OCCURRENCES);
+    Ok(result
+        .dates
+        .iter()
+        .map(|date| date.with_timezone(&start.timezone()))
+        .collect())
+}
+
+/// rrule only accepts a UTC `UNTIL` with a zoned first occurrence, so dates and floating times
+/// are read in that zone. A date includes the whole day.
+fn normalize_until(rule: &str, tz: Tz) -> anyhow::Result<String> {
+    let parts = rule
+        .split(';')
+        .map(|part| match part.split_once('=') {
+            Some((name, value)) if name.eq_ignore_ascii_case("UNTIL") && !value.ends_with('Z') => {
+                let until = match parse_time_value(value, None, tz)? {
+                    IcsTime::Date(date) => {
+                        local_midnight(tz, date + Duration::days(1))? - Duration::seconds(1)
+                    }
+                    IcsTime::DateTime(date_time) => date_time,
+                };
+                Ok(format!(
+                    "UNTIL={}",
+                    until.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+                ))
+            }
+            _ => Ok(part.to_string()),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(parts.join(";"))
+}
+
+enum IcsTime {
+    Date(NaiveDate),
+    DateTime(DateTime<Tz>),
+}
+
+fn start_of(time: IcsTime, tz: Tz) -> anyhow::Result<DateTime<Tz>> {
+    match time {
+        IcsTime::Date(date) => local_midnight(tz, date),
+        IcsTime::DateTime(date_time) => Ok(date_time),
+    }
+}
+
+fn local_midnight(tz: Tz, date: NaiveDate) -> anyhow::Result<DateTime<Tz>> {
+    tz.from_local_datetime(&date.and_hms_opt(0, 0, 0).context("Invalid date")?)
+        .earliest()
+        .context(format!("No midnight on {} in {}", date, tz))
+}
+
+fn parse_time(property: &Property, tz: Tz) -> anyhow::Result<IcsTime> {
+    let value = property
+        .value
+        .as_deref()
+        .context(format!("Missing value for {}", property.name))?;
+    parse_time_value(value, param(property, "TZID"), tz)
+}
+
+/// Floating times and unknown `TZID`s, such as Windows zone names, use the device timezone.
+fn parse_time_value(value: &str, tzid: Option<&str>, tz: Tz) -> anyhow::Result<IcsTime> {
+    let value = value.trim();
+    if value.len() == 8 {
+        return Ok(IcsTime::Date(NaiveDate::parse_from_str(value, "%Y%m%d")?));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?;
+        return Ok(IcsTime::DateTime(Tz::UTC.from_utc_datetime(&naive)));
+    }
+    let zone = tzid
+        .and_then(|tzid| {
+            tzid.trim_matches('"')
+                .trim_start_matches('/')
+                .parse::<Tz>()
+                .ok()
+        })
+        .unwrap_or(tz);
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+    let date_time = zone
+        .from_local_datetime(&naive)
+        .earliest()
+        .context(format!("Invalid local time {} in {}", value, zone))?;
+    Ok(IcsTime::DateTime(date_time))
+}
+
+fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value
+        .strip_prefix('P')
+        .context(format!("Invalid duration {}", value))?;
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let amount: i64 = number.parse()?;
+                number.clear();
+                duration += match c {
+                    'W' => Duration::weeks(amount),
+                    'D' => Duration::days(amount),
+                    'H' => Duration::hours(amount),
+                    'M' => Duration::minutes(amount),
+                    _ => Duration::seconds(amount),
+                };
+            }
+            _ => return Err(anyhow!("Invalid duration {}", value)),
+        }
+    }
+    Ok(if negative { -duration } else { duration })
+}
+
+fn property<'a>(event: &'a IcalEvent, name: &str) -> Option<&'a Property> {
+    event
+        .properties
+        .iter()
+        .find(|property| property.name == name)
+}
+
+fn param<'a>(property: &'a Property, name: &str) -> Option<&'a str> {
+    property
+        .params
+        .iter()
+        .flatten()
+        .find(|(param, _)| param.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+}
+
+fn text(event: &IcalEvent, name: &str) -> Option<String> {
+    let value = property(event, name)?.value.as_deref()?;
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(escaped) => result.push(escaped),
+            None => {}
+        }
+    }
+    Some(result.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::Builder;
+
+    fn config() -> AppCalendarConfig {
+        AppCalendarConfig {
+            sources: vec![],
+            timezone: "America/Los_Angeles".to_string(),
+            days: 7,
+            max_events: 10,
+            time_format: "%H:%M".to_string(),
+        }
+    }
+
+    fn agenda(now: &str) -> CalendarContext {
+        let calendars = parse_calendars(include_str!("sample.ics")).unwrap();
+        let tz: Tz = "America/Los_Angeles".parse().unwrap();
+        let now = DateTime::parse_from_rfc3339(now)
+            .unwrap()
+            .with_timezone(&tz);
+        create_agenda(&calendars, now, &config()).unwrap()
+    }
+
+    fn titles(events: &[CalendarEvent]) -> Vec<(&str, &str, &str)> {
+        events
+            .iter()
+            .map(|event| {
+                (
+                    event.date.as_str(),
+                    event.start_time.as_str(),
+                    event.title.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_should_expand_recurring_events_in_device_timezone() {
+        let agenda = agenda("2025-03-10T08:00:00-07:00");
+        assert_eq!(
+            titles(&agenda.today),
+            vec![
+                ("2025-03-10", "00:00", "Spring break"),
+                ("2025-03-10", "06:30", "Standup"),
+                ("2025-03-10", "12:00", "Lunch & learn"),
+            ]
+        );
+        assert_eq!(
+            titles(&agenda.upcoming),
+            vec![
+                ("2025-03-12", "07:00", "Standup (moved)"),
+                ("2025-03-13", "10:00", "Deploy"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_keep_local_time_of_recurrences_across_dst() {
+        // The standup is at 09:30 in New York, 06:30 in Los Angeles on both sides of the change.
+        let agenda = agenda("2025-03-07T08:00:00-08:00");
+        assert_eq!(
+            titles(&agenda.today),
+            vec![("2025-03-07", "06:30", "Standup")]
+        );
+        assert_eq!(agenda.today[0].start, "2025-03-07T06:30:00-08:00");
+    }
+
+    #[test]
+    fn it_should_describe_events_for_agenda_templates() {
+        let agenda = agenda("2025-03-10T08:00:00-07:00");
+        let lunch = &agenda.today[2];
+        assert_eq!(
+            lunch,
+            &CalendarEvent {
+                title: "Lunch & learn".to_string(),
+                location: Some("Room 4, 2nd floor".to_string()),
+                all_day: false,
+                start: "2025-03-10T12:00:00-07:00".to_string(),
+                end: "2025-03-10T13:30:00-07:00".to_string(),
+                date: "2025-03-10".to_string(),
+                weekday: "Monday".to_string(),
+                start_time: "12:00".to_string(),
+                end_time: "13:30".to_string(),
+            }
+        );
+        assert!(agenda.today[0].all_day);
+        assert_eq!(agenda.today[0].end, "2025-03-12T00:00:00-07:00");
+    }
+
+    fn agenda_of(events: &str, now: &str) -> CalendarContext {
+        let calendars = parse_calendars(&format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n",
+            events
+        ))
+        .unwrap();
+        let now = DateTime::parse_from_rfc3339(now)
+            .unwrap()
+            .with_timezone(&Tz::America__Los_Angeles);
+        create_agenda(
+            &calendars,
+            now,
+            &AppCalendarConfig {
+                days: 14,
+                ..config()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_should_end_all_day_rules_on_until_date() {
+        let agenda = agenda_of(
+            "BEGIN:VEVENT\r\nUID:bins\r\nSUMMARY:Bins\r\nDTSTART;VALUE=DATE:20250303\r\nRRULE:FREQ=WEEKLY;UNTIL=20250317\r\nEND:VEVENT\r\n",
+            "2025-03-10T08:00:00-07:00",
+        );
+        assert_eq!(titles(&agenda.today), vec![("2025-03-10", "00:00", "Bins")]);
+        assert_eq!(
+            titles(&agenda.upcoming),
+            vec![("2025-03-17", "00:00", "Bins")]
+        );
+    }
+
+    #[test]
+    fn it_should_end_floating_rules_on_until_time() {
+        let agenda = agenda_of(
+            "BEGIN:VEVENT\r\nUID:walk\r\nSUMMARY:Walk\r\nDTSTART:20250310T090000\r\nDURATION:PT30M\r\nRRULE:FREQ=DAILY;UNTIL=20250312T090000\r\nEND:VEVENT\r\n",
+            "2025-03-10T08:00:00-07:00",
+        );
+        assert_eq!(titles(&agenda.today), vec![("2025-03-10", "09:00", "Walk")]);
+        assert_eq!(
+            titles(&agenda.upcoming),
+            vec![
+                ("2025-03-11", "09:00", "Walk"),
+                ("2025-03-12", "09:00", "Walk")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_drop_occurrences_with_cancelled_overrides() {
+        let agenda = agenda_of(
+            "BEGIN:VEVENT\r\nUID:gym\r\nSUMMARY:Gym\r\nDTSTART:20250310T180000\r\nRRULE:FREQ=DAILY;COUNT=3\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nUID:gym\r\nSUMMARY:Gym\r\nRECURRENCE-ID:20250311T180000\r\nDTSTART:20250311T180000\r\nSTATUS:CANCELLED\r\nEND:VEVENT\r\n",
+            "2025-03-10T08:00:00-07:00",
+        );
+        assert_eq!(titles(&agenda.today), vec![("2025-03-10", "18:00", "Gym")]);
+        assert_eq!(
+            titles(&agenda.upcoming),
+            vec![("2025-03-12", "18:00", "Gym")]
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_time_format() {
+        let calendars = parse_calendars(include_str!("sample.ics")).unwrap();
+        let now = DateTime::parse_from_rfc3339("2025-03-10T08:00:00-07:00")
+            .unwrap()
+            .with_timezone(&Tz::America__Los_Angeles);
+        let config = AppCalendarConfig {
+            time_format: "%Q".to_string(),
+            ..config()
+        };
+        let error = create_agenda(&calendars, now, &config).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid time format %Q");
+    }
+
+    #[test]
+    fn it_should_parse_durations() {
+        assert_eq!(parse_duration("PT1H30M").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("P1W2D").unwrap(), Duration::days(9));
+        assert_eq!(parse_duration("-PT15M").unwrap(), Duration::minutes(-15));
+        assert!(parse_duration("1H").is_err());
+    }
+
+    #[tokio::test]
+    async fn it_should_load_calendar_from_file() {
+        let mut ics = Builder::new().suffix(".ics").tempfile().unwrap();
+        write!(
+            ics,
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:1\r\nSUMMARY:Release\r\nDTSTART:20090214T010000Z\r\nDTEND:20090214T020000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+        )
+        .unwrap();
+        let mut registry = ContextRegistry::default();
+        registry.register(CalendarContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.calendar]
+                sources = ["{}"]
+                timezone = "UTC"
+
+                [devices.context.calendar]
+                timezone = "Europe/Berlin"
+                "#,
+                ics.path().display()
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["calendar".to_string()])
+            .await
+            .unwrap();
+        // The test clock is 2009-02-14 00:31 in Berlin.
+        assert_eq!(contexts["calendar"]["today"][0]["title"], json!("Release"));
+        assert_eq!(
+            contexts["calendar"]["today"][0]["start_time"],
+            json!("02:00")
+        );
+        assert_eq!(contexts["calendar"]["upcoming"], json!([]));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::api::AppState;
+use crate::context::time::{FormatTime, parse_timezone};
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize)]
+pub struct AppClockConfig {
+    pub timezone: String,
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default)]
+    pub world_clocks: Vec<AppWorldClock>,
+    #[serde(default)]
+    pub countdowns: Vec<AppCountdown>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppWorldClock {
+    pub name: String,
+    pub timezone: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppCountdown {
+    pub name: String,
+    /// A date, a local date and time, or an RFC 3339 timestamp.
+    pub date: String,
+}
+
+fn default_time_format() -> String {
+    "%H:%M".to_string()
+}
+
+fn default_date_format() -> String {
+    "%A, %B %-d".to_string()
+}
+
+impl ContextConfig for AppClockConfig {
+    fn name() -> &'static str {
+        "clock"
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ClockContext {
+    pub time: String,
+    pub date: String,
+    pub iso: String,
+    pub timestamp: i64,
+    pub timezone: String,
+    pub world_clocks: Vec<WorldClock>,
+    pub countdowns: Vec<Countdown>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct WorldClock {
+    pub name: String,
+    pub timezone: String,
+    pub time: String,
+    pub date: String,
+    pub utc_offset: String,
+    /// The calendar day relative to the device, e.g. `1` when it's already tomorrow there.
+    pub day_offset: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Countdown {
+    pub name: String,
+    pub date: String,
+    /// Calendar days from today in the device timezone, negative once the date has passed.
+    pub days: i64,
+    pub seconds: i64,
+    pub text: String,
+}
+
+pub struct ClockContextProvider;
+
+impl ContextProvider for ClockContextProvider {
+    type Config = AppClockConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        _friendly_id: &str,
+        config: AppClockConfig,
+    ) -> anyhow::Result<Value> {
+        let now = DateTime::<Utc>::from(app_state.clock.now());
+        Ok(serde_json::to_value(create_clock_context(now, &config)?)?)
+    }
+}
+
+fn create_clock_context(
+    now: DateTime<Utc>,
+    config: &AppClockConfig,
+) -> anyhow::Result<ClockContext> {
+    let tz = parse_timezone(&config.timezone)?;
+    let local = now.with_timezone(&tz);
+
+    let world_clocks = config
+        .world_clocks
+        .iter()
+        .map(|clock| {
+            let there = now.with_timezone(&parse_timezone(&clock.timezone)?);
+            Ok(WorldClock {
+                name: clock.name.clone(),
+                timezone: clock.timezone.clone(),
+                time: there.format_time(&config.time_format)?,
+                date: there.format_time(&config.date_format)?,
+                utc_offset: there.format("%:z").to_string(),
+                day_offset: (there.date_naive() - local.date_naive()).num_days(),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let countdowns = config
+        .countdowns
+        .iter()
+        .map(|countdown| {
+            let target = parse_target(&countdown.date, &tz)
+                .context(format!("Invalid countdown {}", countdown.name))?;
+            let days = (target.date_naive() - local.date_naive()).num_days();
+            Ok(Countdown {
+                name: countdown.name.clone(),
+                date: target.format_time(&config.date_format)?,
+                days,
+                seconds: (target.with_timezone(&Utc) - now).num_seconds(),
+                text: relative_days(days),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(ClockContext {
+        time: local.format_time(&config.time_format)?,
+        date: local.format_time(&config.date_format)?,
+        iso: local.to_rfc3339(),
+        timestamp: now.timestamp(),
+        timezone: config.timezone.clone(),
+        world_clocks,
+        countdowns,
+    })
+}
+
+fn parse_target(value: &str, tz: &Tz) -> anyhow::Result<DateTime<Tz>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(tz));
+    }
+    let local = match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => date.and_hms_opt(0, 0, 0).context("Invalid date")?,
+        Err(_) => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+            .context(format!("Invalid date {}", value))?,
+    };
+    tz.from_local_datetime(&local)
+        .earliest()
+        .context(format!("Invalid local time {} in {}", value, tz))
+}
+
+fn relative_days(days: i64) -> String {
+    match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        days if days > 1 => format!("in {} days", days),
+        days => format!("{} days ago", -days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn it_should_load_clock_context() {
+        let mut registry = ContextRegistry::default();
+        registry.register(ClockContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            r#"
+            [default_context.clock]
+            timezone = "America/Los_Angeles"
+            world_clocks = [
+                { name = "Tokyo", timezone = "Asia/Tokyo" },
+                { name = "London", timezone = "Europe/London" },
+            ]
+            countdowns = [
+                { name = "Launch", date = "2009-02-25" },
+                { name = "Valentine's Day", date = "2009-02-14T18:00" },
+                { name = "Kickoff", date = "2009-02-01T09:00:00Z" },
+            ]
+            "#,
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["clock".to_string()])
+            .await
+            .unwrap();
+        // The test clock is 2009-02-13 15:31:30 in Los Angeles.
+        assert_eq!(
+            contexts["clock"],
+            json!({
+                "time": "15:31",
+                "date": "Friday, February 13",
+                "iso": "2009-02-13T15:31:30-08:00",
+                "timestamp": 1234567890,
+                "timezone": "America/Los_Angeles",
+                "world_clocks": [
+                    {
+                        "name": "Tokyo",
+                        "timezone": "Asia/Tokyo",
+                        "time": "08:31",
+                        "date": "Saturday, February 14",
+                        "utc_offset": "+09:00",
+                        "day_offset": 1
+                    },
+                    {
+                        "name": "London",
+                        "timezone": "Europe/London",
+                        "time": "23:31",
+                        "date": "Friday, February 13",
+                        "utc_offset": "+00:00",
+                        "day_offset": 0
+                    }
+                ],
+                "countdowns": [
+                    {
+                        "name": "Launch",
+                        "date": "Wednesday, February 25",
+                        "days": 12,
+                        "seconds": 980910,
+                        "text": "in 12 days"
+                    },
+                    {
+                        "name": "Valentine's Day",
+                        "date": "Saturday, February 14",
+                        "days": 1,
+                        "seconds": 95310,
+                        "text": "tomorrow"
+                    },
+                    {
+                        "name": "Kickoff",
+                        "date": "Sunday, February 1",
+                        "days": -12,
+                        "seconds": -1089090,
+                        "text": "12 days ago"
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_formats() {
+        let config = AppClockConfig {
+            timezone: "UTC".to_string(),
+            time_format: "%Q".to_string(),
+            date_format: default_date_format(),
+            world_clocks: vec![],
+            countdowns: vec![],
+        };
+        let error = create_clock_context(Utc::now(), &config).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid time format %Q");
+    }
+
+    #[test]
+    fn it_should_reject_invalid_countdown_dates() {
+        let config = AppClockConfig {
+            timezone: "UTC".to_string(),
+            time_format: default_time_format(),
+            date_format: default_date_format(),
+            world_clocks: vec![],
+            countdowns: vec![AppCountdown {
+                name: "Launch".to_string(),
+                date: "next tuesday".to_string(),
+            }],
+        };
+        let error = create_clock_context(Utc::now(), &config).unwrap_err();
+        assert!(
+            format!("{:#}", error)
+                .starts_with("Invalid countdown Launch: Invalid date next tuesday")
+        );
+    }
+}
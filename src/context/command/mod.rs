@@ -0,0 +1,157 @@
+use crate::api::AppState;
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct AppCommandContextConfig {
+    /// Context keys mapped to the commands producing them.
+    pub commands: HashMap<String, AppCommand>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppCommand {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl ContextConfig for AppCommandContextConfig {
+    fn name() -> &'static str {
+        "command"
+    }
+}
+
+pub struct CommandContextProvider;
+
+impl ContextProvider for CommandContextProvider {
+    type Config = AppCommandContextConfig;
+
+    async fn fetch(
+        &self,
+        _app_state: &AppState,
+        _friendly_id: &str,
+        config: AppCommandContextConfig,
+    ) -> anyhow::Result<Value> {
+        let mut result = Map::new();
+        for (name, command) in &config.commands {
+            let value = run_command(command)
+                .await
+                .context(format!("Command {} failed", name))?;
+            result.insert(name.clone(), value);
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+/// Runs the command and parses its stdout as JSON, the child is killed when it times out.
+async fn run_command(command: &AppCommand) -> anyhow::Result<Value> {
+    info!("Running command {:?}", command);
+    let child = Command::new(&command.command)
+        .args(&command.args)
+        .envs(&command.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context(format!("Failed to start {}", command.command))?;
+    let output = tokio::time::timeout(
+        Duration::from_secs(command.timeout_secs),
+        child.wait_with_output(),
+    )
+    .await
+    .map_err(|_| anyhow!("Timed out after {}s", command.timeout_secs))??;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(anyhow!("Exited with {}: {}", output.status, stderr.trim()));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| anyhow!("Invalid JSON output: {}: {}", e, stderr.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use serde_json::json;
+
+    fn shell(script: &str) -> AppCommand {
+        AppCommand {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: HashMap::new(),
+            timeout_secs: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_should_include_stderr_in_errors() {
+        let error = run_command(&shell("echo 'disk not mounted' >&2; exit 3"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Exited with exit status: 3: disk not mounted"
+        );
+
+        let error = run_command(&shell("echo not json")).await.unwrap_err();
+        assert!(error.to_string().starts_with("Invalid JSON output"));
+    }
+
+    #[tokio::test]
+    async fn it_should_time_out() {
+        let command = AppCommand {
+            timeout_secs: 1,
+            ..shell("sleep 5")
+        };
+        let error = run_command(&command).await.unwrap_err();
+        assert_eq!(error.to_string(), "Timed out after 1s");
+    }
+
+    #[tokio::test]
+    async fn it_should_load_command_output() {
+        let mut registry = ContextRegistry::default();
+        registry.register(CommandContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            r#"
+            [default_context.command.commands.backup]
+            command = "sh"
+            args = ["-c", "printf '{\"last_run\": \"%s\", \"ok\": true}' \"$1\"", "sh", "2025-05-01"]
+
+            [default_context.command.commands.build]
+            command = "sh"
+            args = ["-c", "echo \"{\\\"branch\\\": \\\"$BRANCH\\\"}\""]
+            env = { BRANCH = "main" }
+            "#,
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["command".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            contexts["command"],
+            json!({
+                "backup": { "last_run": "2025-05-01", "ok": true },
+                "build": { "branch": "main" }
+            })
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::api::AppState;
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+#[derive(Debug, Deserialize)]
+pub struct AppDataConfig {
+    /// Context keys mapped to `.json`, `.toml` or `.csv` files.
+    pub files: HashMap<String, PathBuf>,
+}
+
+impl ContextConfig for AppDataConfig {
+    fn name() -> &'static str {
+        "data"
+    }
+}
+
+/// Keeps parsed files until their modification time changes.
+#[derive(Default)]
+pub struct DataContextProvider {
+    cache: Mutex<HashMap<PathBuf, (SystemTime, Value)>>,
+}
+
+impl ContextProvider for DataContextProvider {
+    type Config = AppDataConfig;
+
+    async fn fetch(
+        &self,
+        _app_state: &AppState,
+        _friendly_id: &str,
+        config: AppDataConfig,
+    ) -> anyhow::Result<Value> {
+        let mut result = Map::new();
+        for (name, path) in &config.files {
+            result.insert(name.clone(), self.read(path)?);
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+impl DataContextProvider {
+    fn read(&self, path: &Path) -> anyhow::Result<Value> {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .context(format!("Failed to read {}", path.display()))?;
+        let mut cache = self.cache.lock().map_err(|e| anyhow!("{}", e))?;
+        if let Some((cached, value)) = cache.get(path)
+            && *cached == modified
+        {
+            return Ok(value.clone());
+        }
+        let value = parse_file(path).context(format!("Failed to parse {}", path.display()))?;
+        cache.insert(path.to_path_buf(), (modified, value.clone()));
+        Ok(value)
+    }
+}
+
+fn parse_file(path: &Path) -> anyhow::Result<Value> {
+    let data = fs::read_to_string(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => Ok(serde_json::from_str(&data)?),
+        Some("toml") => Ok(serde_json::to_value(toml::from_str::<toml::Table>(&data)?)?),
+        Some("csv") => parse_csv(&data),
+        _ => Err(anyhow!("Unsupported data file type")),
+    }
+}
+
+/// Turns each row into an object keyed by the header, numeric cells become numbers and empty
+/// cells become null.
+fn parse_csv(data: &str) -> anyhow::Result<Value> {
+    let mut reader = csv::Reader::from_reader(data.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(header, cell)| (header.to_string(), parse_cell(cell)))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+fn parse_cell(cell: &str) -> Value {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return Value::Null;
+    }
+    if let Ok(integer) = cell.parse::<i64>() {
+        return Value::from(integer);
+    }
+    match cell.parse::<f64>().ok().and_then(Number::from_f64) {
+        Some(number) => Value::Number(number),
+        None => Value::String(cell.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use serde_json::json;
+    use std::fs::File;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_should_parse_csv_rows() {
+        let rows = parse_csv("day,visitors,note\nMon,120,\nTue,98.5,rain\n").unwrap();
+        assert_eq!(
+            rows,
+            json!([
+                { "day": "Mon", "visitors": 120, "note": null },
+                { "day": "Tue", "visitors": 98.5, "note": "rain" }
+            ])
+        );
+    }
+
+    #[test]
+    fn it_should_reread_changed_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, r#"{"state": "green"}"#).unwrap();
+        let provider = DataContextProvider::default();
+        assert_eq!(provider.read(&path).unwrap(), json!({ "state": "green" }));
+
+        fs::write(&path, r#"{"state": "red"}"#).unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(provider.read(&path).unwrap(), json!({ "state": "red" }));
+    }
+
+    #[tokio::test]
+    async fn it_should_load_configured_files() {
+        let dir = tempdir().unwrap();
+        let toml = dir.path().join("office.toml");
+        fs::write(&toml, "name = \"HQ\"\n[wifi]\nssid = \"guest\"\n").unwrap();
+        let json = dir.path().join("queue.json");
+        fs::write(&json, "[1, 2, 3]").unwrap();
+
+        let mut registry = ContextRegistry::default();
+        registry.register(DataContextProvider::default());
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.data]
+                files = {{ office = "{}", queue = "{}" }}
+                "#,
+                toml.display(),
+                json.display()
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["data".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            contexts["data"],
+            json!({
+                "office": { "name": "HQ", "wifi": { "ssid": "guest" } },
+                "queue": [1, 2, 3]
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_error_on_unsupported_file_type() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "hello").unwrap();
+        assert!(DataContextProvider::default().read(&path).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::api::AppState;
+use crate::context::{ContextConfig, ContextProvider};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::SystemTime;
+
+/// The battery percentage is interpolated linearly between the two voltages.
+#[derive(Debug, Deserialize)]
+pub struct AppDeviceContextConfig {
+    #[serde(default = "default_battery_empty_voltage")]
+    pub battery_empty_voltage: f64,
+    #[serde(default = "default_battery_full_voltage")]
+    pub battery_full_voltage: f64,
+}
+
+fn default_battery_empty_voltage() -> f64 {
+    3.3
+}
+
+fn default_battery_full_voltage() -> f64 {
+    4.2
+}
+
+impl ContextConfig for AppDeviceContextConfig {
+    fn name() -> &'static str {
+        "device"
+    }
+}
+
+/// Reported in the headers of the device's display requests, values that fail to parse are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceTelemetry {
+    pub battery_voltage: Option<f64>,
+    pub rssi: Option<i32>,
+    pub fw_version: Option<String>,
+    pub refresh_rate: Option<u64>,
+    pub last_seen: SystemTime,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct DeviceContext {
+    pub friendly_id: String,
+    pub fw_version: Option<String>,
+    pub battery_voltage: Option<f64>,
+    pub battery_percentage: Option<u8>,
+    pub rssi: Option<i32>,
+    pub signal: Option<&'static str>,
+    pub signal_bars: Option<u8>,
+    pub refresh_rate: Option<u64>,
+    pub last_seen: Option<String>,
+    pub last_seen_seconds: Option<i64>,
+}
+
+/// Fields are `None` until the device has made a display request since the server started.
+pub struct DeviceContextProvider;
+
+impl ContextProvider for DeviceContextProvider {
+    type Config = AppDeviceContextConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        friendly_id: &str,
+        config: AppDeviceContextConfig,
+    ) -> anyhow::Result<Value> {
+        let now = DateTime::<Utc>::from(app_state.clock.now());
+        let telemetry = app_state.get_telemetry(friendly_id);
+        Ok(serde_json::to_value(create_device_context(
+            friendly_id,
+            telemetry.as_ref(),
+            now,
+            &config,
+        ))?)
+    }
+}
+
+fn create_device_context(
+    friendly_id: &str,
+    telemetry: Option<&DeviceTelemetry>,
+    now: DateTime<Utc>,
+    config: &AppDeviceContextConfig,
+) -> DeviceContext {
+    let last_seen = telemetry.map(|telemetry| DateTime::<Utc>::from(telemetry.last_seen));
+    let signal = telemetry.and_then(|telemetry| telemetry.rssi).map(signal);
+    DeviceContext {
+        friendly_id: friendly_id.to_string(),
+        fw_version: telemetry.and_then(|telemetry| telemetry.fw_version.clone()),
+        battery_voltage: telemetry.and_then(|telemetry| telemetry.battery_voltage),
+        battery_percentage: telemetry
+            .and_then(|telemetry| telemetry.battery_voltage)
+            .map(|voltage| battery_percentage(voltage, config)),
+        rssi: telemetry.and_then(|telemetry| telemetry.rssi),
+        signal: signal.map(|(name, _)| name),
+        signal_bars: signal.map(|(_, bars)| bars),
+        refresh_rate: telemetry.and_then(|telemetry| telemetry.refresh_rate),
+        last_seen: last_seen.map(|last_seen| last_seen.to_rfc3339()),
+        last_seen_seconds: last_seen.map(|last_seen| (now - last_seen).num_seconds()),
+    }
+}
+
+fn battery_percentage(voltage: f64, config: &AppDeviceContextConfig) -> u8 {
+    let range = config.battery_full_voltage - config.battery_empty_voltage;
+    if range <= 0.0 {
+        return 0;
+    }
+    ((voltage - config.battery_empty_voltage) / range * 100.0)
+        .clamp(0.0, 100.0)
+        .round() as u8
+}
+
+fn signal(rssi: i32) -> (&'static str, u8) {
+    match rssi {
+        rssi if rssi >= -55 => ("excellent", 4),
+        rssi if rssi >= -67 => ("good", 3),
+        rssi if rssi >= -75 => ("fair", 2),
+        _ => ("weak", 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn it_should_load_recorded_telemetry() {
+        let mut registry = ContextRegistry::default();
+        registry.register(DeviceContextProvider);
+        let (app_state, _config) = new_test_app_state("", registry);
+
+        let contexts = load_contexts(
+            app_state.clone(),
+            "fake_friendly_id",
+            vec!["device".to_string()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(contexts["device"]["friendly_id"], json!("fake_friendly_id"));
+        assert_eq!(contexts["device"]["battery_percentage"], json!(null));
+
+        app_state.record_telemetry(
+            "fake_friendly_id",
+            DeviceTelemetry {
+                battery_voltage: Some(3.75),
+                rssi: Some(-70),
+                fw_version: Some("1.5.2".to_string()),
+                refresh_rate: Some(900),
+                last_seen: SystemTime::UNIX_EPOCH + Duration::from_secs(1234567590),
+            },
+        );
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["device".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            contexts["device"],
+            json!({
+                "friendly_id": "fake_friendly_id",
+                "fw_version": "1.5.2",
+                "battery_voltage": 3.75,
+                "battery_percentage": 50,
+                "rssi": -70,
+                "signal": "fair",
+                "signal_bars": 2,
+                "refresh_rate": 900,
+                "last_seen": "2009-02-13T23:26:30+00:00",
+                "last_seen_seconds": 300
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_clamp_battery_percentage() {
+        let config = AppDeviceContextConfig {
+            battery_empty_voltage: default_battery_empty_voltage(),
+            battery_full_voltage: default_battery_full_voltage(),
+        };
+        assert_eq!(battery_percentage(4.35, &config), 100);
+        assert_eq!(battery_percentage(3.1, &config), 0);
+        assert_eq!(battery_percentage(3.93, &config), 70);
+        assert_eq!(signal(-48), ("excellent", 4));
+        assert_eq!(signal(-90), ("weak", 1));
+    }
+}
@@ -0,0 +1,351 @@
+use crate::api::AppState;
+use crate::context::time::{FormatTime, parse_timezone};
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use feed_rs::model::{Entry, Feed};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+const ELLIPSIS: &str = "…";
+
+#[derive(Debug, Deserialize)]
+pub struct AppFeedConfig {
+    /// URLs of RSS or Atom feeds, items from all feeds are merged.
+    pub sources: Vec<String>,
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(default = "default_count")]
+    pub count: usize,
+    #[serde(default = "default_summary_length")]
+    pub summary_length: usize,
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_count() -> usize {
+    10
+}
+
+fn default_summary_length() -> usize {
+    280
+}
+
+fn default_time_format() -> String {
+    "%Y-%m-%d %H:%M".to_string()
+}
+
+impl ContextConfig for AppFeedConfig {
+    fn name() -> &'static str {
+        "feed"
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FeedContext {
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FeedItem {
+    pub title: String,
+    pub source: String,
+    pub link: Option<String>,
+    pub published: Option<String>,
+    pub published_time: Option<String>,
+    pub summary: String,
+}
+
+pub struct FeedContextProvider;
+
+impl ContextProvider for FeedContextProvider {
+    type Config = AppFeedConfig;
+
+    async fn fetch(
+        &self,
+        _app_state: &AppState,
+        _friendly_id: &str,
+        config: AppFeedConfig,
+    ) -> anyhow::Result<Value> {
+        info!("Getting feeds with config {:?}", config);
+        let mut feeds = vec![];
+        for source in &config.sources {
+            let data = reqwest::get(source)
+                .await
+                .context(format!("Failed to request {}", source))?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            let feed = feed_rs::parser::parse(data.as_ref())
+                .context(format!("Failed to parse feed {}", source))?;
+            feeds.push((source.clone(), feed));
+        }
+        Ok(serde_json::to_value(create_feed_context(&feeds, &config)?)?)
+    }
+}
+
+fn create_feed_context(
+    feeds: &[(String, Feed)],
+    config: &AppFeedConfig,
+) -> anyhow::Result<FeedContext> {
+    let tz = parse_timezone(&config.timezone)?;
+    let mut entries: Vec<(&str, &Entry)> = feeds
+        .iter()
+        .flat_map(|(source, feed)| {
+            let name = feed
+                .title
+                .as_ref()
+                .map(|title| title.content.as_str())
+                .unwrap_or(source);
+            feed.entries.iter().map(move |entry| (name, entry))
+        })
+        .collect();
+    // Newest first, entries without a date go last.
+    entries.sort_by_key(|(_, entry)| std::cmp::Reverse(published(entry)));
+
+    let items = entries
+        .into_iter()
+        .take(config.count)
+        .map(|(source, entry)| {
+            let published = published(entry).map(|date| date.with_timezone(&tz));
+            let summary = entry
+                .summary
+                .as_ref()
+                .map(|summary| summary.content.clone())
+                .or_else(|| {
+                    entry
+                        .content
+                        .as_ref()
+                        .and_then(|content| content.body.clone())
+                })
+                .unwrap_or_default();
+            Ok(FeedItem {
+                title: entry
+                    .title
+                    .as_ref()
+                    .map(|title| strip_html(&title.content))
+                    .unwrap_or_default(),
+                source: strip_html(source),
+                link: entry.links.first().map(|link| link.href.clone()),
+                published: published.map(|date| date.to_rfc3339()),
+                published_time: published
+                    .map(|date| date.format_time(&config.time_format))
+                    .transpose()?,
+                summary: truncate(&strip_html(&summary), config.summary_length),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(FeedContext { items })
+}
+
+fn published(entry: &Entry) -> Option<DateTime<Utc>> {
+    entry.published.or(entry.updated)
+}
+
+/// Drops tags, `script` and `style` contents, decodes entities and collapses whitespace.
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find(['<', '&']) {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with('&') {
+            let (decoded, length) = decode_entity(rest);
+            text.push_str(&decoded);
+            rest = &rest[length..];
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = rest[1..end].trim_start_matches('/').to_lowercase();
+        let name = tag.split([' ', '/', '\t', '\n']).next().unwrap_or("");
+        rest = &rest[end + 1..];
+        if name == "script" || name == "style" {
+            let close = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(index) => rest[index..]
+                    .find('>')
+                    .map_or("", |end| &rest[index + end + 1..]),
+                None => "",
+            };
+        }
+        text.push(' ');
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entity(text: &str) -> (String, usize) {
+    let Some(end) = text.find(';').filter(|end| *end <= 10) else {
+        return ("&".to_string(), 1);
+    };
+    let entity = &text[1..end];
+    let decoded = match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "hellip" => Some('…'),
+        "mdash" => Some('—'),
+        "ndash" => Some('–'),
+        _ => entity.strip_prefix('#').and_then(|number| {
+            match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => number.parse().ok(),
+            }
+            .and_then(char::from_u32)
+        }),
+    };
+    match decoded {
+        Some(c) => (c.to_string(), end + 1),
+        None => ("&".to_string(), 1),
+    }
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(length).collect();
+    let truncated = match truncated.rfind(' ') {
+        Some(index) if index > 0 => &truncated[..index],
+        _ => &truncated,
+    };
+    format!("{}{}", truncated.trim_end(), ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::{new_test_app_state, serve};
+    use crate::context::{ContextRegistry, load_contexts};
+    use axum::Router;
+    use axum::http::header;
+    use axum::routing::get;
+    use serde_json::json;
+
+    fn config() -> AppFeedConfig {
+        AppFeedConfig {
+            sources: vec![],
+            timezone: "Europe/Berlin".to_string(),
+            count: 3,
+            summary_length: 40,
+            time_format: "%d.%m. %H:%M".to_string(),
+        }
+    }
+
+    fn sample_feeds() -> Vec<(String, Feed)> {
+        vec![
+            (
+                "https://news.example.com/rss".to_string(),
+                feed_rs::parser::parse(include_str!("sample_rss.xml").as_bytes()).unwrap(),
+            ),
+            (
+                "https://changelog.example.com/atom".to_string(),
+                feed_rs::parser::parse(include_str!("sample_atom.xml").as_bytes()).unwrap(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn it_should_merge_feeds_newest_first() {
+        let context = create_feed_context(&sample_feeds(), &config()).unwrap();
+        let items: Vec<(&str, &str)> = context
+            .items
+            .iter()
+            .map(|item| (item.source.as_str(), item.title.as_str()))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                ("Product changelog", "Release 2.4 & dark mode"),
+                ("Example News", "Council approves new bike lanes"),
+                ("Product changelog", "Release 2.3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_strip_html_from_summaries() {
+        let context = create_feed_context(&sample_feeds(), &config()).unwrap();
+        let item = &context.items[1];
+        assert_eq!(item.summary, "The plan adds 12 km of protected lanes…");
+        assert_eq!(item.published.as_deref(), Some("2025-05-02T10:30:00+02:00"));
+        assert_eq!(item.published_time.as_deref(), Some("02.05. 10:30"));
+        assert_eq!(
+            item.link.as_deref(),
+            Some("https://news.example.com/bike-lanes")
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_time_format() {
+        let config = AppFeedConfig {
+            time_format: "%Q".to_string(),
+            ..config()
+        };
+        let error = create_feed_context(&sample_feeds(), &config).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid time format %Q");
+    }
+
+    #[test]
+    fn it_should_decode_entities_and_drop_scripts() {
+        assert_eq!(
+            strip_html(
+                "<p>Fish &amp; chips<br/>&#8364;5 &#x2014; <script>alert('x')</script>today&nbsp;only</p>"
+            ),
+            "Fish & chips €5 — today only"
+        );
+        assert_eq!(strip_html("a < b && c"), "a < b && c");
+    }
+
+    #[tokio::test]
+    async fn it_should_fetch_configured_feeds() {
+        let url = serve(
+            Router::new()
+                .route(
+                    "/rss",
+                    get(|| async {
+                        (
+                            [(header::CONTENT_TYPE, "application/rss+xml")],
+                            include_str!("sample_rss.xml"),
+                        )
+                    }),
+                )
+                .route("/atom", get(|| async { include_str!("sample_atom.xml") })),
+        )
+        .await;
+
+        let mut registry = ContextRegistry::default();
+        registry.register(FeedContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.feed]
+                sources = ["{url}/rss", "{url}/atom"]
+
+                [devices.context.feed]
+                count = 1
+                "#
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["feed".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            contexts["feed"]["items"][0]["title"],
+            json!("Release 2.4 & dark mode")
+        );
+        assert_eq!(contexts["feed"]["items"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::api::AppState;
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::info;
+use url::Url;
+
+#[derive(Debug, Deserialize)]
+pub struct AppHomeAssistantConfig {
+    pub base_url: String,
+    /// A long-lived access token from the Home Assistant user profile.
+    pub token: String,
+    pub entities: Vec<String>,
+}
+
+impl ContextConfig for AppHomeAssistantConfig {
+    fn name() -> &'static str {
+        "home_assistant"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct State {
+    entity_id: String,
+    state: String,
+    #[serde(default)]
+    attributes: Map<String, Value>,
+    last_changed: Option<String>,
+    last_updated: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct HomeAssistantEntity {
+    pub entity_id: String,
+    pub state: String,
+    /// The state as a number for sensors, `None` for states such as "on" or "unavailable".
+    pub value: Option<f64>,
+    pub friendly_name: Option<String>,
+    pub unit: Option<String>,
+    pub attributes: Map<String, Value>,
+    pub last_changed: Option<String>,
+    pub last_updated: Option<String>,
+}
+
+impl From<State> for HomeAssistantEntity {
+    fn from(state: State) -> HomeAssistantEntity {
+        let attribute = |name: &str| {
+            state
+                .attributes
+                .get(name)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        HomeAssistantEntity {
+            value: state
+                .state
+                .parse()
+                .ok()
+                .filter(|value: &f64| value.is_finite()),
+            friendly_name: attribute("friendly_name"),
+            unit: attribute("unit_of_measurement"),
+            entity_id: state.entity_id,
+            state: state.state,
+            attributes: state.attributes,
+            last_changed: state.last_changed,
+            last_updated: state.last_updated,
+        }
+    }
+}
+
+/// Entities are keyed by their id, e.g. `home_assistant["sensor.outside_temperature"]`.
+pub struct HomeAssistantContextProvider;
+
+impl ContextProvider for HomeAssistantContextProvider {
+    type Config = AppHomeAssistantConfig;
+
+    async fn fetch(
+        &self,
+        _app_state: &AppState,
+        _friendly_id: &str,
+        config: AppHomeAssistantConfig,
+    ) -> anyhow::Result<Value> {
+        info!(
+            "Getting Home Assistant states for {:?} from {}",
+            config.entities, config.base_url
+        );
+        let client = reqwest::Client::new();
+        let mut result = Map::new();
+        for entity_id in &config.entities {
+            let mut url = Url::parse(&config.base_url)?;
+            url.path_segments_mut()
+                .map_err(|_| anyhow!("Invalid base URL {}", config.base_url))?
+                .pop_if_empty()
+                .extend(["api", "states", entity_id]);
+            let state: State = client
+                .get(url)
+                .bearer_auth(&config.token)
+                .send()
+                .await
+                .context(format!("Failed to request state of {}", entity_id))?
+                .error_for_status()
+                .context(format!("Failed to get state of {}", entity_id))?
+                .json()
+                .await?;
+            result.insert(
+                entity_id.clone(),
+                serde_json::to_value(HomeAssistantEntity::from(state))?,
+            );
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::{new_test_app_state, serve};
+    use crate::context::{ContextRegistry, load_contexts};
+    use axum::extract::Path;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+
+    async fn state(
+        Path(entity_id): Path<String>,
+        headers: HeaderMap,
+    ) -> Result<Json<Value>, StatusCode> {
+        if headers["authorization"] != "Bearer fake_token" {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        match entity_id.as_str() {
+            "sensor.outside_temperature" => Ok(Json(json!({
+                "entity_id": "sensor.outside_temperature",
+                "state": "18.4",
+                "attributes": {
+                    "unit_of_measurement": "°C",
+                    "friendly_name": "Outside"
+                },
+                "last_changed": "2025-05-01T10:00:00+00:00",
+                "last_updated": "2025-05-01T10:05:00+00:00"
+            }))),
+            "binary_sensor.front_door" => Ok(Json(json!({
+                "entity_id": "binary_sensor.front_door",
+                "state": "off",
+                "attributes": { "device_class": "door", "friendly_name": "Front door" },
+                "last_changed": "2025-05-01T08:00:00+00:00",
+                "last_updated": "2025-05-01T08:00:00+00:00"
+            }))),
+            _ => Err(StatusCode::NOT_FOUND),
+        }
+    }
+
+    async fn new_mock_app_state(
+        entities: &str,
+        token: &str,
+    ) -> (AppState, tempfile::NamedTempFile) {
+        let url = serve(Router::new().route("/ha/api/states/{entity_id}", get(state))).await;
+
+        let mut registry = ContextRegistry::default();
+        registry.register(HomeAssistantContextProvider);
+        new_test_app_state(
+            &format!(
+                r#"
+                [default_context.home_assistant]
+                base_url = "{url}/ha/"
+                token = "{token}"
+                entities = {entities}
+                "#
+            ),
+            registry,
+        )
+    }
+
+    #[tokio::test]
+    async fn it_should_load_entity_states() {
+        let (app_state, _config) = new_mock_app_state(
+            r#"["sensor.outside_temperature", "binary_sensor.front_door"]"#,
+            "fake_token",
+        )
+        .await;
+
+        let contexts = load_contexts(
+            app_state,
+            "fake_friendly_id",
+            vec!["home_assistant".to_string()],
+        )
+        .await
+        .unwrap();
+        let temperature = &contexts["home_assistant"]["sensor.outside_temperature"];
+        assert_eq!(temperature["value"], json!(18.4));
+        assert_eq!(temperature["unit"], json!("°C"));
+        assert_eq!(temperature["friendly_name"], json!("Outside"));
+        let door = &contexts["home_assistant"]["binary_sensor.front_door"];
+        assert_eq!(door["state"], json!("off"));
+        assert_eq!(door["value"], json!(null));
+        assert_eq!(door["attributes"]["device_class"], json!("door"));
+    }
+
+    #[tokio::test]
+    async fn it_should_error_on_unknown_entity_or_token() {
+        let (app_state, _config) = new_mock_app_state(r#"["sensor.missing"]"#, "fake_token").await;
+        let result = load_contexts(
+            app_state,
+            "fake_friendly_id",
+            vec!["home_assistant".to_string()],
+        )
+        .await;
+        assert!(result.is_err());
+
+        let (app_state, _config) =
+            new_mock_app_state(r#"["binary_sensor.front_door"]"#, "wrong_token").await;
+        let result = load_contexts(
+            app_state,
+            "fake_friendly_id",
+            vec!["home_assistant".to_string()],
+        )
+        .await;
+        assert!(format!("{:#}", result.unwrap_err()).contains("401 Unauthorized"));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::api::AppState;
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Deserialize, Debug)]
+pub struct AppHttpConfig {
+    /// Context keys mapped to the requests producing them.
+    pub requests: HashMap<String, AppHttpRequest>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AppHttpRequest {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    /// Context keys mapped to JSONPath expressions, the whole response is used when empty.
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+impl ContextConfig for AppHttpConfig {
+    fn name() -> &'static str {
+        "http"
+    }
+}
+
+pub struct HttpContextProvider;
+
+impl ContextProvider for HttpContextProvider {
+    type Config = AppHttpConfig;
+
+    async fn fetch(
+        &self,
+        _app_state: &AppState,
+        _friendly_id: &str,
+        config: AppHttpConfig,
+    ) -> anyhow::Result<Value> {
+        let mut result = Map::new();
+        for (name, request) in &config.requests {
+            let data = request_json(request)
+                .await
+                .context(format!("Request {} failed", name))?;
+            result.insert(name.clone(), extract_fields(&data, &request.fields)?);
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+async fn request_json(config: &AppHttpRequest) -> anyhow::Result<Value> {
+    let method = Method::from_str(&config.method.to_uppercase())
+        .context(format!("Invalid HTTP method: {}", config.method))?;
+    let mut request = reqwest::Client::new().request(method, &config.url);
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    if let Some(body) = &config.body {
+        request = request.body(body.clone());
+    }
+    let result = request
+        .send()
+        .await
+        .context(format!("Failed to request {}", config.url))?
+        .error_for_status()?
+        .json()
+        .await
+        .context(format!("Failed to parse JSON from {}", config.url))?;
+    Ok(result)
+}
+
+/// A path matching a single node yields its value, no match yields null and several matches
+/// yield a list.
+fn extract_fields(data: &Value, fields: &HashMap<String, String>) -> anyhow::Result<Value> {
+    if fields.is_empty() {
+        return Ok(data.clone());
+    }
+    let mut result = Map::new();
+    for (name, expression) in fields {
+        let path = JsonPath::parse(expression)
+            .map_err(|e| anyhow!("Invalid JSONPath for field {}: {}", name, e))?;
+        let value = match path.query(data).all().as_slice() {
+            [] => Value::Null,
+            [node] => (*node).clone(),
+            nodes => Value::Array(nodes.iter().map(|node| (*node).clone()).collect()),
+        };
+        result.insert(name.clone(), value);
+    }
+    Ok(Value::Object(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::{new_test_app_state, serve};
+    use crate::context::{ContextRegistry, load_contexts};
+    use axum::Json;
+    use axum::Router;
+    use axum::http::HeaderMap;
+    use axum::routing::{get, post};
+    use serde_json::json;
+
+    #[test]
+    fn it_should_extract_fields_with_json_path() {
+        let data = json!({
+            "status": { "healthy": true },
+            "queues": [
+                { "name": "email", "depth": 12 },
+                { "name": "billing", "depth": 3 }
+            ]
+        });
+        let fields = HashMap::from([
+            ("healthy".to_string(), "$.status.healthy".to_string()),
+            ("depths".to_string(), "$.queues[*].depth".to_string()),
+            (
+                "billing".to_string(),
+                "$.queues[?@.name == 'billing'].depth".to_string(),
+            ),
+            ("missing".to_string(), "$.status.uptime".to_string()),
+        ]);
+        assert_eq!(
+            extract_fields(&data, &fields).unwrap(),
+            json!({
+                "healthy": true,
+                "depths": [12, 3],
+                "billing": 3,
+                "missing": null
+            })
+        );
+        assert_eq!(extract_fields(&data, &HashMap::new()).unwrap(), data);
+    }
+
+    #[test]
+    fn it_should_error_on_invalid_json_path() {
+        let fields = HashMap::from([("broken".to_string(), "$.[".to_string())]);
+        assert!(extract_fields(&json!({}), &fields).is_err());
+    }
+
+    #[tokio::test]
+    async fn it_should_request_configured_urls() {
+        let url = serve(
+            Router::new()
+                .route(
+                    "/stats",
+                    post(|headers: HeaderMap, body: String| async move {
+                        Json(json!({
+                            "token": headers["authorization"].to_str().unwrap(),
+                            "body": body,
+                            "stats": { "signups": 42, "revenue": 1234.5 }
+                        }))
+                    }),
+                )
+                .route("/status", get(|| async { Json(json!({ "open": 2 })) })),
+        )
+        .await;
+
+        let mut registry = ContextRegistry::default();
+        registry.register(HttpContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.http.requests.stats]
+                url = "{url}/stats"
+                method = "post"
+                body = '{{"range": "week"}}'
+                headers = {{ Authorization = "Bearer default" }}
+                fields = {{ signups = "$.stats.signups", token = "$.token", body = "$.body" }}
+
+                [default_context.http.requests.status]
+                url = "{url}/status"
+                "#
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["http".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            contexts["http"],
+            json!({
+                "stats": {
+                    "signups": 42,
+                    "token": "Bearer default",
+                    "body": r#"{"range": "week"}"#
+                },
+                "status": { "open": 2 }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_should_name_failed_request() {
+        let url = serve(Router::new()).await;
+        let config = AppHttpConfig {
+            requests: HashMap::from([(
+                "missing".to_string(),
+                AppHttpRequest {
+                    url: format!("{url}/missing"),
+                    method: default_method(),
+                    headers: HashMap::new(),
+                    body: None,
+                    fields: HashMap::new(),
+                },
+            )]),
+        };
+        let (app_state, _config) = new_test_app_state("", ContextRegistry::default());
+        let error = HttpContextProvider
+            .fetch(&app_state, "fake_friendly_id", config)
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", error)
+                .starts_with("Request missing failed: HTTP status client error (404")
+        );
+    }
+}
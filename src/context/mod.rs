@@ -1,13 +1,92 @@
 use crate::api::AppState;
-use crate::context::weather::{AppWeatherConfig, create_weather_context};
+use anyhow::Context;
+use futures_util::future::BoxFuture;
+use serde::de::DeserializeOwned;
 use serde_json::{Map, Value};
+use std::collections::HashMap;
 
-mod weather;
+pub mod astronomy;
+pub mod calendar;
+pub mod clock;
+pub mod command;
+pub mod data;
+pub mod device;
+pub mod feed;
+pub mod home_assistant;
+pub mod http;
+pub mod prometheus;
+pub mod time;
+pub mod transit;
+pub mod weather;
 
 pub trait ContextConfig {
     fn name() -> &'static str;
 }
 
+/// A source of template data. The provider's config is looked up under
+/// `ContextConfig::name()` and merged with the device's overrides before each fetch.
+pub trait ContextProvider: Send + Sync + 'static {
+    type Config: ContextConfig + DeserializeOwned + Send;
+
+    fn fetch(
+        &self,
+        app_state: &AppState,
+        friendly_id: &str,
+        config: Self::Config,
+    ) -> impl Future<Output = anyhow::Result<Value>> + Send;
+}
+
+trait DynContextProvider: Send + Sync {
+    fn load<'a>(
+        &'a self,
+        app_state: &'a AppState,
+        friendly_id: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Value>>;
+}
+
+impl<P: ContextProvider> DynContextProvider for P {
+    fn load<'a>(
+        &'a self,
+        app_state: &'a AppState,
+        friendly_id: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Value>> {
+        Box::pin(async move {
+            let config: P::Config = app_state.get_context_config(friendly_id)?;
+            self.fetch(app_state, friendly_id, config).await
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct ContextRegistry {
+    providers: HashMap<&'static str, Box<dyn DynContextProvider>>,
+}
+
+impl ContextRegistry {
+    pub fn builtin() -> ContextRegistry {
+        let mut registry = ContextRegistry::default();
+        registry
+            .register(weather::WeatherContextProvider::default())
+            .register(http::HttpContextProvider)
+            .register(calendar::CalendarContextProvider)
+            .register(feed::FeedContextProvider)
+            .register(data::DataContextProvider::default())
+            .register(command::CommandContextProvider)
+            .register(home_assistant::HomeAssistantContextProvider)
+            .register(prometheus::PrometheusContextProvider)
+            .register(transit::TransitContextProvider::default())
+            .register(astronomy::AstronomyContextProvider)
+            .register(clock::ClockContextProvider)
+            .register(device::DeviceContextProvider);
+        registry
+    }
+
+    pub fn register<P: ContextProvider>(&mut self, provider: P) -> &mut ContextRegistry {
+        self.providers.insert(P::Config::name(), Box::new(provider));
+        self
+    }
+}
+
 pub async fn load_contexts(
     app_state: AppState,
     friendly_id: &str,
@@ -15,14 +94,155 @@ pub async fn load_contexts(
 ) -> anyhow::Result<Map<String, Value>> {
     let mut result = Map::new();
     for context_name in contexts {
-        let context = match context_name.as_str() {
-            "weather" => {
-                let context_config: AppWeatherConfig = app_state.get_context_config(friendly_id)?;
-                create_weather_context(&context_config).await?
-            }
-            _ => return Err(anyhow::anyhow!("Unknown context: {}", context_name)),
-        };
-        result.insert(context_name, serde_json::to_value(context)?);
+        let provider = app_state
+            .context_registry
+            .providers
+            .get(context_name.as_str())
+            .context(format!("Unknown context: {}", context_name))?;
+        let context = provider
+            .load(&app_state, friendly_id)
+            .await
+            .context(format!("Failed to load context {}", context_name))?;
+        result.insert(context_name, context);
     }
     Ok(result)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::api::{AppServerConfig, Clock};
+    use axum::Router;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+    use tempfile::{Builder, NamedTempFile};
+    use tokio::net::TcpListener;
+
+    struct FakeClock;
+    impl Clock for FakeClock {
+        fn now(&self) -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1234567890)
+        }
+    }
+
+    /// Creates an app state for a single device `fake_friendly_id`, `context` is appended to
+    /// the config so tests can add `[default_context.*]` and `[devices.context.*]` tables.
+    pub(crate) fn new_test_app_state(
+        context: &str,
+        context_registry: ContextRegistry,
+    ) -> (AppState, NamedTempFile) {
+        let mut config = Builder::new()
+            .suffix(".toml")
+            .tempfile()
+            .expect("Failed to create temp file");
+        write!(
+            config,
+            r#"
+            setup_image_path = "src/display/blank.bmp"
+            base_url = "http://example.localhost"
+            display_image_timeout = 60
+            templates_path = "templates"
+            default_context_path = "templates/default.json"
+            fonts_path = "fonts"
+
+            [[devices]]
+            mac_address = "fake_mac_address"
+            friendly_id = "fake_friendly_id"
+            api_key = "fake_api_key"
+            setup_expiry = "9999-01-01T00:00:00Z"
+
+            [[devices.playlist]]
+            filename = "test.svg.jinja"
+            contexts = [ ]
+
+            {}
+        "#,
+            context
+        )
+        .expect("Failed to write config");
+
+        let app_state = AppState {
+            server_config: AppServerConfig {
+                listen: "0.0.0.0:9080".to_string(),
+                config_path: config.path().to_path_buf(),
+            },
+            clock: Arc::new(FakeClock),
+            context_registry: Arc::new(context_registry),
+            telemetry: Arc::default(),
+        };
+        (app_state, config)
+    }
+
+    /// Serves the router on a free local port and returns its base URL.
+    pub(crate) async fn serve(router: Router) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    #[derive(Deserialize)]
+    struct GreetingConfig {
+        name: String,
+        greeting: String,
+    }
+
+    impl ContextConfig for GreetingConfig {
+        fn name() -> &'static str {
+            "greeting"
+        }
+    }
+
+    struct GreetingContextProvider;
+
+    impl ContextProvider for GreetingContextProvider {
+        type Config = GreetingConfig;
+
+        async fn fetch(
+            &self,
+            _app_state: &AppState,
+            friendly_id: &str,
+            config: GreetingConfig,
+        ) -> anyhow::Result<Value> {
+            Ok(json!({
+                "message": format!("{} {} from {}", config.greeting, config.name, friendly_id)
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn it_should_load_registered_context_with_device_overrides() {
+        let mut registry = ContextRegistry::default();
+        registry.register(GreetingContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            r#"
+            [default_context.greeting]
+            name = "world"
+            greeting = "hello"
+
+            [devices.context.greeting]
+            name = "office"
+            "#,
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["greeting".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            contexts["greeting"],
+            json!({ "message": "hello office from fake_friendly_id" })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_should_error_on_unknown_context() {
+        let (app_state, _config) = new_test_app_state("", ContextRegistry::builtin());
+        let result =
+            load_contexts(app_state, "fake_friendly_id", vec!["unknown".to_string()]).await;
+        assert_eq!(result.unwrap_err().to_string(), "Unknown context: unknown");
+    }
+}
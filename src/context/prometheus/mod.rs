@@ -0,0 +1,376 @@
+use crate::api::AppState;
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::UNIX_EPOCH;
+use tracing::info;
+use url::Url;
+
+#[derive(Debug, Deserialize)]
+pub struct AppPrometheusConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub queries: HashMap<String, AppPrometheusQuery>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppPrometheusQuery {
+    pub query: String,
+    /// Runs a range query over the last `range_secs` seconds, instant queries are used otherwise.
+    pub range_secs: Option<u64>,
+    #[serde(default = "default_step_secs")]
+    pub step_secs: u64,
+}
+
+fn default_step_secs() -> u64 {
+    60
+}
+
+impl ContextConfig for AppPrometheusConfig {
+    fn name() -> &'static str {
+        "prometheus"
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct QueryResult {
+    /// The scalar result, or the latest value of the first series.
+    pub value: Option<f64>,
+    pub series: Vec<Series>,
+}
+
+/// Range series are aligned to the query steps, so `values` can be passed to the chart
+/// functions with missing samples as gaps.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Series {
+    pub metric: Map<String, Value>,
+    pub value: Option<f64>,
+    pub timestamps: Vec<f64>,
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    status: String,
+    data: Option<ResponseData>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "resultType", content = "result", rename_all = "lowercase")]
+enum ResponseData {
+    Scalar(Sample),
+    String(Sample),
+    Vector(Vec<InstantSeries>),
+    Matrix(Vec<RangeSeries>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Sample(f64, String);
+
+#[derive(Debug, Deserialize)]
+struct InstantSeries {
+    #[serde(default)]
+    metric: Map<String, Value>,
+    value: Sample,
+}
+
+#[derive(Debug, Deserialize)]
+struct RangeSeries {
+    #[serde(default)]
+    metric: Map<String, Value>,
+    values: Vec<Sample>,
+}
+
+struct Range {
+    start: u64,
+    end: u64,
+    step: u64,
+}
+
+pub struct PrometheusContextProvider;
+
+impl ContextProvider for PrometheusContextProvider {
+    type Config = AppPrometheusConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        _friendly_id: &str,
+        config: AppPrometheusConfig,
+    ) -> anyhow::Result<Value> {
+        let now = app_state
+            .clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut result = Map::new();
+        for (name, query) in &config.queries {
+            let value = run_query(&config, query, now)
+                .await
+                .context(format!("Prometheus query {} failed", name))?;
+            result.insert(name.clone(), serde_json::to_value(value)?);
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+async fn run_query(
+    config: &AppPrometheusConfig,
+    query: &AppPrometheusQuery,
+    now: u64,
+) -> anyhow::Result<QueryResult> {
+    info!("Running Prometheus query {:?}", query);
+    let mut url = Url::parse(&config.url)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Invalid URL {}", config.url))?
+        .pop_if_empty()
+        .extend(["api", "v1"]);
+    let range = query.range_secs.map(|range_secs| {
+        let step = query.step_secs.max(1);
+        let end = now - now % step;
+        Range {
+            start: end.saturating_sub(range_secs),
+            end,
+            step,
+        }
+    });
+    match &range {
+        Some(range) => {
+            url.path_segments_mut()
+                .map_err(|_| anyhow!("Invalid URL {}", config.url))?
+                .push("query_range");
+            url.query_pairs_mut()
+                .append_pair("query", &query.query)
+                .append_pair("start", &range.start.to_string())
+                .append_pair("end", &range.end.to_string())
+                .append_pair("step", &range.step.to_string());
+        }
+        None => {
+            url.path_segments_mut()
+                .map_err(|_| anyhow!("Invalid URL {}", config.url))?
+                .push("query");
+            url.query_pairs_mut()
+                .append_pair("query", &query.query)
+                .append_pair("time", &now.to_string());
+        }
+    }
+
+    let mut request = reqwest::Client::new().get(url);
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    let response: Response = request.send().await?.json().await?;
+    if response.status != "success" {
+        return Err(anyhow!("{}", response.error.unwrap_or(response.status)));
+    }
+    let data = response.data.context("Missing data in response")?;
+    Ok(parse_result(data, range.as_ref()))
+}
+
+fn parse_result(data: ResponseData, range: Option<&Range>) -> QueryResult {
+    let series: Vec<Series> = match data {
+        ResponseData::Scalar(sample) | ResponseData::String(sample) => {
+            return QueryResult {
+                value: parse_value(&sample.1),
+                series: vec![],
+            };
+        }
+        ResponseData::Vector(vector) => vector
+            .into_iter()
+            .map(|series| Series {
+                metric: series.metric,
+                value: parse_value(&series.value.1),
+                timestamps: vec![series.value.0],
+                values: vec![parse_value(&series.value.1)],
+            })
+            .collect(),
+        ResponseData::Matrix(matrix) => matrix
+            .into_iter()
+            .map(|series| align(series, range))
+            .collect(),
+    };
+    QueryResult {
+        value: series.first().and_then(|series| series.value),
+        series,
+    }
+}
+
+fn align(series: RangeSeries, range: Option<&Range>) -> Series {
+    let value = series
+        .values
+        .last()
+        .and_then(|sample| parse_value(&sample.1));
+    let Some(range) = range else {
+        return Series {
+            metric: series.metric,
+            value,
+            timestamps: series.values.iter().map(|sample| sample.0).collect(),
+            values: series
+                .values
+                .iter()
+                .map(|sample| parse_value(&sample.1))
+                .collect(),
+        };
+    };
+    let count = ((range.end - range.start) / range.step + 1) as usize;
+    let mut values = vec![None; count];
+    for sample in &series.values {
+        let index = ((sample.0 - range.start as f64) / range.step as f64).round();
+        if index >= 0.0 && (index as usize) < count {
+            values[index as usize] = parse_value(&sample.1);
+        }
+    }
+    Series {
+        metric: series.metric,
+        value,
+        timestamps: (0..count)
+            .map(|index| (range.start + range.step * index as u64) as f64)
+            .collect(),
+        values,
+    }
+}
+
+/// Prometheus encodes sample values as strings, NaN and infinities become `None`.
+fn parse_value(value: &str) -> Option<f64> {
+    value.parse().ok().filter(|value: &f64| value.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::{new_test_app_state, serve};
+    use crate::context::{ContextRegistry, load_contexts};
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+
+    async fn query(Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+        Json(match params["query"].as_str() {
+            "sum(queue_depth)" => json!({
+                "status": "success",
+                "data": {
+                    "resultType": "vector",
+                    "result": [
+                        { "metric": {}, "value": [params["time"].parse::<f64>().unwrap(), "42"] }
+                    ]
+                }
+            }),
+            _ => json!({
+                "status": "error",
+                "errorType": "bad_data",
+                "error": "parse error"
+            }),
+        })
+    }
+
+    async fn query_range(Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+        let start: f64 = params["start"].parse().unwrap();
+        let step: f64 = params["step"].parse().unwrap();
+        Json(json!({
+            "status": "success",
+            "data": {
+                "resultType": "matrix",
+                "result": [{
+                    "metric": { "slo": "checkout" },
+                    "values": [
+                        [start, "0.5"],
+                        [start + step * 2.0, "NaN"],
+                        [start + step * 3.0, "1.25"]
+                    ]
+                }]
+            }
+        }))
+    }
+
+    #[tokio::test]
+    async fn it_should_run_instant_and_range_queries() {
+        let url = serve(
+            Router::new()
+                .route("/prometheus/api/v1/query", get(query))
+                .route("/prometheus/api/v1/query_range", get(query_range)),
+        )
+        .await;
+
+        let mut registry = ContextRegistry::default();
+        registry.register(PrometheusContextProvider);
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.prometheus]
+                url = "{url}/prometheus"
+
+                [default_context.prometheus.queries.queue]
+                query = "sum(queue_depth)"
+
+                [default_context.prometheus.queries.burn]
+                query = "slo:burn_rate:1h"
+                range_secs = 180
+                step_secs = 60
+                "#
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(
+            app_state,
+            "fake_friendly_id",
+            vec!["prometheus".to_string()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(contexts["prometheus"]["queue"]["value"], json!(42.0));
+        // The test clock is 1234567890, range queries end on the last whole step.
+        assert_eq!(
+            contexts["prometheus"]["burn"],
+            json!({
+                "value": 1.25,
+                "series": [{
+                    "metric": { "slo": "checkout" },
+                    "value": 1.25,
+                    "timestamps": [1234567680.0, 1234567740.0, 1234567800.0, 1234567860.0],
+                    "values": [0.5, null, null, 1.25]
+                }]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_should_report_query_errors() {
+        let url = serve(Router::new().route("/api/v1/query", get(query))).await;
+
+        let config = AppPrometheusConfig {
+            url,
+            headers: HashMap::new(),
+            queries: HashMap::new(),
+        };
+        let query = AppPrometheusQuery {
+            query: "sum(".to_string(),
+            range_secs: None,
+            step_secs: 60,
+        };
+        let error = run_query(&config, &query, 0).await.unwrap_err();
+        assert_eq!(error.to_string(), "parse error");
+    }
+
+    #[test]
+    fn it_should_parse_scalar_results() {
+        let data: ResponseData = serde_json::from_value(json!({
+            "resultType": "scalar",
+            "result": [1234567890.0, "0.999"]
+        }))
+        .unwrap();
+        assert_eq!(
+            parse_result(data, None),
+            QueryResult {
+                value: Some(0.999),
+                series: vec![]
+            }
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::anyhow;
+use chrono::{DateTime, TimeZone};
+use chrono_tz::Tz;
+use std::fmt::{Display, Write};
+
+pub fn parse_timezone(timezone: &str) -> anyhow::Result<Tz> {
+    timezone
+        .parse()
+        .map_err(|e| anyhow!("Invalid timezone {}: {}", timezone, e))
+}
+
+/// Formatting with a configured strftime format. chrono's `format(..).to_string()` panics when the
+/// format is invalid, e.g. `%Q`, so the error is returned instead.
+pub trait FormatTime {
+    fn format_time(&self, format: &str) -> anyhow::Result<String>;
+}
+
+impl<T: TimeZone> FormatTime for DateTime<T>
+where
+    T::Offset: Display,
+{
+    fn format_time(&self, format: &str) -> anyhow::Result<String> {
+        let mut result = String::new();
+        write!(result, "{}", self.format(format))
+            .map_err(|_| anyhow!("Invalid time format {}", format))?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn it_should_parse_timezone() {
+        assert_eq!(parse_timezone("Europe/Berlin").unwrap(), Tz::Europe__Berlin);
+        assert!(parse_timezone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn it_should_fail_on_invalid_format() {
+        let time = DateTime::<Utc>::from_timestamp(1234567890, 0).unwrap();
+        assert_eq!(time.format_time("%H:%M").unwrap(), "23:31");
+        assert_eq!(
+            time.format_time("%Q").unwrap_err().to_string(),
+            "Invalid time format %Q"
+        );
+    }
+}
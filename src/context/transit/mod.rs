@@ -0,0 +1,870 @@
+use crate::api::AppState;
+use crate::context::time::{FormatTime, parse_timezone};
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use prost::Message;
+use realtime::{FeedMessage, STOP_SKIPPED, TRIP_CANCELED, TripUpdate};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use tracing::{info, warn};
+use zip::ZipArchive;
+
+mod realtime;
+
+#[derive(Debug, Deserialize)]
+pub struct AppTransitConfig {
+    /// Path to the static GTFS zip, it is parsed again when the file changes.
+    pub gtfs_path: PathBuf,
+    /// Path or URL of a GTFS-realtime TripUpdates feed.
+    pub realtime: Option<String>,
+    pub timezone: String,
+    pub stops: Vec<String>,
+    /// Route ids or short names, departures of all routes are shown when empty.
+    #[serde(default)]
+    pub routes: Vec<String>,
+    #[serde(default = "default_count")]
+    pub count: usize,
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+}
+
+fn default_count() -> usize {
+    3
+}
+
+fn default_time_format() -> String {
+    "%H:%M".to_string()
+}
+
+impl ContextConfig for AppTransitConfig {
+    fn name() -> &'static str {
+        "transit"
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TransitContext {
+    pub stops: Vec<StopDepartures>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StopDepartures {
+    pub stop_id: String,
+    pub name: String,
+    pub departures: Vec<Departure>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Departure {
+    pub route: String,
+    pub route_id: String,
+    pub headsign: Option<String>,
+    pub trip_id: String,
+    pub scheduled: String,
+    pub departure: String,
+    pub time: String,
+    pub minutes: i64,
+    pub delay_minutes: i64,
+    pub realtime: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct StopRow {
+    stop_id: String,
+    stop_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteRow {
+    route_id: String,
+    route_short_name: Option<String>,
+    route_long_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TripRow {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+    trip_headsign: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StopTimeRow {
+    trip_id: String,
+    arrival_time: Option<String>,
+    departure_time: Option<String>,
+    stop_id: String,
+    stop_sequence: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarRow {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarDateRow {
+    service_id: String,
+    date: String,
+    exception_type: u8,
+}
+
+impl CalendarRow {
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        let runs = match date.weekday() {
+            Weekday::Mon => self.monday,
+            Weekday::Tue => self.tuesday,
+            Weekday::Wed => self.wednesday,
+            Weekday::Thu => self.thursday,
+            Weekday::Fri => self.friday,
+            Weekday::Sat => self.saturday,
+            Weekday::Sun => self.sunday,
+        };
+        let date = date.format("%Y%m%d").to_string();
+        runs == 1 && self.start_date <= date && date <= self.end_date
+    }
+}
+
+struct StopTime {
+    trip_id: String,
+    stop_id: String,
+    stop_sequence: u32,
+    /// Seconds after the start of the service day, can exceed 24 hours.
+    departure: i64,
+}
+
+/// The parts of a static feed needed for departures at a set of stops.
+struct Timetable {
+    stop_names: HashMap<String, String>,
+    routes: HashMap<String, RouteRow>,
+    trips: HashMap<String, TripRow>,
+    stop_times: Vec<StopTime>,
+    calendar: HashMap<String, CalendarRow>,
+    calendar_dates: HashMap<(String, String), u8>,
+}
+
+impl Timetable {
+    fn load(path: &Path, stops: &[String]) -> anyhow::Result<Timetable> {
+        info!("Loading GTFS feed {} for stops {:?}", path.display(), stops);
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let stop_ids: HashSet<&str> = stops.iter().map(String::as_str).collect();
+
+        let stop_times: Vec<StopTime> =
+            read_csv(&mut archive, "stop_times.txt", |row: &StopTimeRow| {
+                stop_ids.contains(row.stop_id.as_str())
+            })?
+            .into_iter()
+            .filter_map(|row| {
+                let time = row.departure_time.or(row.arrival_time)?;
+                Some(parse_gtfs_time(&time).map(|departure| StopTime {
+                    trip_id: row.trip_id,
+                    stop_id: row.stop_id,
+                    stop_sequence: row.stop_sequence,
+                    departure,
+                }))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let trip_ids: HashSet<&str> = stop_times.iter().map(|s| s.trip_id.as_str()).collect();
+        let trips = read_csv(&mut archive, "trips.txt", |row: &TripRow| {
+            trip_ids.contains(row.trip_id.as_str())
+        })?;
+        let stop_names = read_csv(&mut archive, "stops.txt", |row: &StopRow| {
+            stop_ids.contains(row.stop_id.as_str())
+        })?;
+        let routes = read_csv(&mut archive, "routes.txt", |_: &RouteRow| true)?;
+        let calendar = match archive.index_for_name("calendar.txt") {
+            Some(_) => read_csv(&mut archive, "calendar.txt", |_: &CalendarRow| true)?,
+            None => vec![],
+        };
+        let calendar_dates = match archive.index_for_name("calendar_dates.txt") {
+            Some(_) => read_csv(&mut archive, "calendar_dates.txt", |_: &CalendarDateRow| {
+                true
+            })?,
+            None => vec![],
+        };
+
+        Ok(Timetable {
+            stop_names: stop_names
+                .into_iter()
+                .map(|row| (row.stop_id, row.stop_name.unwrap_or_default()))
+                .collect(),
+            routes: routes
+                .into_iter()
+                .map(|row| (row.route_id.clone(), row))
+                .collect(),
+            trips: trips
+                .into_iter()
+                .map(|row| (row.trip_id.clone(), row))
+                .collect(),
+            stop_times,
+            calendar: calendar
+                .into_iter()
+                .map(|row| (row.service_id.clone(), row))
+                .collect(),
+            calendar_dates: calendar_dates
+                .into_iter()
+                .map(|row| ((row.service_id, row.date), row.exception_type))
+                .collect(),
+        })
+    }
+
+    fn runs_on(&self, service_id: &str, date: NaiveDate) -> bool {
+        let key = (service_id.to_string(), date.format("%Y%m%d").to_string());
+        match self.calendar_dates.get(&key) {
+            Some(1) => true,
+            Some(2) => false,
+            _ => self
+                .calendar
+                .get(service_id)
+                .is_some_and(|calendar| calendar.runs_on(date)),
+        }
+    }
+
+    fn route_name(&self, route_id: &str) -> String {
+        self.routes
+            .get(route_id)
+            .and_then(|route| {
+                route
+                    .route_short_name
+                    .clone()
+                    .filter(|name| !name.is_empty())
+                    .or(route.route_long_name.clone())
+            })
+            .unwrap_or(route_id.to_string())
+    }
+
+    /// Looks at yesterday's service too, since its trips can run past midnight.
+    fn departures(
+        &self,
+        stop_id: &str,
+        now: DateTime<Tz>,
+        config: &AppTransitConfig,
+        updates: &HashMap<&str, Vec<&TripUpdate>>,
+    ) -> anyhow::Result<Vec<Departure>> {
+        let tz = now.timezone();
+        let mut departures = vec![];
+        for offset in -1..=1 {
+            let date = now.date_naive() + Duration::days(offset);
+            let service_day = service_day_start(tz, date)?;
+            let service_date = date.format("%Y%m%d").to_string();
+            for stop_time in self.stop_times.iter().filter(|s| s.stop_id == stop_id) {
+                let Some(trip) = self.trips.get(&stop_time.trip_id) else {
+                    continue;
+                };
+                let route = self.route_name(&trip.route_id);
+                if !config.routes.is_empty()
+                    && !config.routes.contains(&trip.route_id)
+                    && !config.routes.contains(&route)
+                {
+                    continue;
+                }
+                if !self.runs_on(&trip.service_id, date) {
+                    continue;
+                }
+                let scheduled = service_day + Duration::seconds(stop_time.departure);
+                let update = updates.get(trip.trip_id.as_str()).and_then(|updates| {
+                    updates.iter().find(|update| {
+                        update
+                            .trip
+                            .as_ref()
+                            .and_then(|trip| trip.start_date.as_deref())
+                            .is_none_or(|start_date| start_date == service_date)
+                    })
+                });
+                let departure = match update {
+                    Some(update) => match realtime_departure(update, stop_time, scheduled) {
+                        Some(departure) => departure,
+                        None => continue,
+                    },
+                    None => scheduled,
+                };
+                if departure < now {
+                    continue;
+                }
+                departures.push(Departure {
+                    route,
+                    route_id: trip.route_id.clone(),
+                    headsign: trip.trip_headsign.clone(),
+                    trip_id: trip.trip_id.clone(),
+                    scheduled: scheduled.to_rfc3339(),
+                    departure: departure.to_rfc3339(),
+                    time: departure.format_time(&config.time_format)?,
+                    minutes: (departure - now).num_minutes(),
+                    delay_minutes: (departure - scheduled).num_minutes(),
+                    realtime: update.is_some(),
+                });
+            }
+        }
+        departures.sort_by(|a, b| a.departure.cmp(&b.departure));
+        departures.truncate(config.count);
+        Ok(departures)
+    }
+}
+
+/// Returns `None` when the trip is cancelled or the stop is skipped. Without an update for the
+/// stop itself, the delay of the closest earlier stop is carried over.
+fn realtime_departure(
+    update: &TripUpdate,
+    stop_time: &StopTime,
+    scheduled: DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+    if update
+        .trip
+        .as_ref()
+        .and_then(|trip| trip.schedule_relationship)
+        == Some(TRIP_CANCELED)
+    {
+        return None;
+    }
+    let updates = &update.stop_time_update;
+    let exact = updates.iter().find(|stop_update| {
+        stop_update.stop_sequence == Some(stop_time.stop_sequence)
+            || (stop_update.stop_sequence.is_none()
+                && stop_update.stop_id.as_deref() == Some(stop_time.stop_id.as_str()))
+    });
+    if let Some(stop_update) = exact {
+        if stop_update.schedule_relationship == Some(STOP_SKIPPED) {
+            return None;
+        }
+        let event = stop_update
+            .departure
+            .as_ref()
+            .or(stop_update.arrival.as_ref());
+        if let Some(time) = event.and_then(|event| event.time) {
+            return Utc
+                .timestamp_opt(time, 0)
+                .single()
+                .map(|time| time.with_timezone(&scheduled.timezone()));
+        }
+        if let Some(delay) = event.and_then(|event| event.delay) {
+            return Some(scheduled + Duration::seconds(delay as i64));
+        }
+    }
+    let delay = updates
+        .iter()
+        .filter(|stop_update| {
+            stop_update
+                .stop_sequence
+                .is_some_and(|sequence| sequence < stop_time.stop_sequence)
+        })
+        .max_by_key(|stop_update| stop_update.stop_sequence)
+        .and_then(|stop_update| {
+            stop_update
+                .departure
+                .as_ref()
+                .or(stop_update.arrival.as_ref())
+                .and_then(|event| event.delay)
+        })
+        .or(update.delay)
+        .unwrap_or(0);
+    Some(scheduled + Duration::seconds(delay as i64))
+}
+
+/// GTFS times count from noon minus 12 hours, which is midnight except on DST change days.
+fn service_day_start(tz: Tz, date: NaiveDate) -> anyhow::Result<DateTime<Tz>> {
+    let noon = date.and_hms_opt(12, 0, 0).context("Invalid date")?;
+    let noon = tz
+        .from_local_datetime(&noon)
+        .earliest()
+        .context(format!("No noon on {} in {}", date, tz))?;
+    Ok(noon - Duration::hours(12))
+}
+
+fn parse_gtfs_time(time: &str) -> anyhow::Result<i64> {
+    let parts: Vec<i64> = time
+        .trim()
+        .split(':')
+        .map(|part| part.parse())
+        .collect::<Result<_, _>>()
+        .context(format!("Invalid GTFS time {}", time))?;
+    match parts.as_slice() {
+        [hours, minutes, seconds] => Ok(hours * 3600 + minutes * 60 + seconds),
+        _ => Err(anyhow!("Invalid GTFS time {}", time)),
+    }
+}
+
+fn read_csv<T: DeserializeOwned>(
+    archive: &mut ZipArchive<File>,
+    name: &str,
+    keep: impl Fn(&T) -> bool,
+) -> anyhow::Result<Vec<T>> {
+    let file = archive
+        .by_name(name)
+        .context(format!("Missing {} in GTFS feed", name))?;
+    let mut rows = vec![];
+    for row in csv::Reader::from_reader(file).deserialize() {
+        let row: T = row.context(format!("Failed to parse {}", name))?;
+        if keep(&row) {
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+async fn read_realtime(source: &str) -> anyhow::Result<FeedMessage> {
+    let data = if source.starts_with("http://") || source.starts_with("https://") {
+        reqwest::get(source)
+            .await
+            .context(format!("Failed to request {}", source))?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec()
+    } else {
+        fs::read(source).context(format!("Failed to read {}", source))?
+    };
+    FeedMessage::decode(data.as_slice()).context(format!("Failed to decode {}", source))
+}
+
+/// How many parsed feeds are kept, one for each GTFS file and set of stops.
+const MAX_CACHED_TIMETABLES: usize = 8;
+
+type TimetableCache = HashMap<(PathBuf, Vec<String>), CachedTimetable>;
+
+struct CachedTimetable {
+    modified: SystemTime,
+    loaded: Instant,
+    timetable: Arc<Timetable>,
+}
+
+/// Keeps the parsed static feed for each set of stops until the zip changes.
+#[derive(Default)]
+pub struct TransitContextProvider {
+    cache: Mutex<TimetableCache>,
+}
+
+impl TransitContextProvider {
+    /// Parsing a large feed takes a while, so it runs on the blocking pool without the cache
+    /// locked. The feed loaded longest ago is dropped once the cache is full.
+    async fn timetable(&self, path: &Path, stops: &[String]) -> anyhow::Result<Arc<Timetable>> {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .context(format!("Failed to read {}", path.display()))?;
+        let key = (path.to_path_buf(), stops.to_vec());
+        if let Some(cached) = self.cache.lock().map_err(|e| anyhow!("{}", e))?.get(&key)
+            && cached.modified == modified
+        {
+            return Ok(cached.timetable.clone());
+        }
+        let (load_path, load_stops) = key.clone();
+        let timetable = Arc::new(
+            tokio::task::spawn_blocking(move || Timetable::load(&load_path, &load_stops))
+                .await?
+                .context(format!("Failed to load {}", path.display()))?,
+        );
+        let mut cache = self.cache.lock().map_err(|e| anyhow!("{}", e))?;
+        if cache.len() >= MAX_CACHED_TIMETABLES
+            && !cache.contains_key(&key)
+            && let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.loaded)
+                .map(|(key, _)| key.clone())
+        {
+            cache.remove(&oldest);
+        }
+        cache.insert(
+            key,
+            CachedTimetable {
+                modified,
+                loaded: Instant::now(),
+                timetable: timetable.clone(),
+            },
+        );
+        Ok(timetable)
+    }
+}
+
+impl ContextProvider for TransitContextProvider {
+    type Config = AppTransitConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        _friendly_id: &str,
+        config: AppTransitConfig,
+    ) -> anyhow::Result<Value> {
+        let timetable = self.timetable(&config.gtfs_path, &config.stops).await?;
+        let feed = match &config.realtime {
+            Some(source) => match read_realtime(source).await {
+                Ok(feed) => Some(feed),
+                Err(e) => {
+                    warn!(
+                        "Using scheduled departures without realtime updates: {:#}",
+                        e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let tz = parse_timezone(&config.timezone)?;
+        let now = DateTime::<Utc>::from(app_state.clock.now()).with_timezone(&tz);
+        Ok(serde_json::to_value(create_transit_context(
+            &timetable,
+            feed.as_ref(),
+            now,
+            &config,
+        )?)?)
+    }
+}
+
+fn create_transit_context(
+    timetable: &Timetable,
+    feed: Option<&FeedMessage>,
+    now: DateTime<Tz>,
+    config: &AppTransitConfig,
+) -> anyhow::Result<TransitContext> {
+    let mut updates: HashMap<&str, Vec<&TripUpdate>> = HashMap::new();
+    for entity in feed.iter().flat_map(|feed| &feed.entity) {
+        if entity.is_deleted == Some(true) {
+            continue;
+        }
+        if let Some(update) = &entity.trip_update
+            && let Some(trip_id) = update
+                .trip
+                .as_ref()
+                .and_then(|trip| trip.trip_id.as_deref())
+        {
+            updates.entry(trip_id).or_default().push(update);
+        }
+    }
+    let stops = config
+        .stops
+        .iter()
+        .map(|stop_id| {
+            Ok(StopDepartures {
+                stop_id: stop_id.clone(),
+                name: timetable
+                    .stop_names
+                    .get(stop_id)
+                    .cloned()
+                    .unwrap_or(stop_id.clone()),
+                departures: timetable.departures(stop_id, now, config, &updates)?,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(TransitContext { stops })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::new_test_app_state;
+    use crate::context::{ContextRegistry, load_contexts};
+    use realtime::{FeedEntity, FeedHeader, StopTimeEvent, StopTimeUpdate, TripDescriptor};
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::{TempDir, tempdir};
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    const FIXTURES: [(&str, &str); 6] = [
+        ("stops.txt", include_str!("fixtures/stops.txt")),
+        ("routes.txt", include_str!("fixtures/routes.txt")),
+        ("trips.txt", include_str!("fixtures/trips.txt")),
+        ("stop_times.txt", include_str!("fixtures/stop_times.txt")),
+        ("calendar.txt", include_str!("fixtures/calendar.txt")),
+        (
+            "calendar_dates.txt",
+            include_str!("fixtures/calendar_dates.txt"),
+        ),
+    ];
+
+    fn fixture_zip() -> (TempDir, PathBuf) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gtfs.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for (name, content) in FIXTURES {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        (dir, path)
+    }
+
+    fn config(routes: &[&str]) -> AppTransitConfig {
+        AppTransitConfig {
+            gtfs_path: PathBuf::new(),
+            realtime: None,
+            timezone: "America/Los_Angeles".to_string(),
+            stops: vec!["S1".to_string(), "S2".to_string()],
+            routes: routes.iter().map(|route| route.to_string()).collect(),
+            count: 3,
+            time_format: "%H:%M".to_string(),
+        }
+    }
+
+    fn now(time: &str) -> DateTime<Tz> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&"America/Los_Angeles".parse::<Tz>().unwrap())
+    }
+
+    fn times(context: &TransitContext, stop: usize) -> Vec<(&str, &str, &str)> {
+        context.stops[stop]
+            .departures
+            .iter()
+            .map(|departure| {
+                (
+                    departure.time.as_str(),
+                    departure.route.as_str(),
+                    departure.trip_id.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    fn trip_update(trip_id: &str, stop_time_update: Vec<StopTimeUpdate>) -> FeedEntity {
+        FeedEntity {
+            id: Some(trip_id.to_string()),
+            is_deleted: None,
+            trip_update: Some(TripUpdate {
+                trip: Some(TripDescriptor {
+                    trip_id: Some(trip_id.to_string()),
+                    start_date: Some("20250505".to_string()),
+                    schedule_relationship: None,
+                    route_id: None,
+                }),
+                stop_time_update,
+                delay: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn it_should_list_next_scheduled_departures() {
+        let (_dir, path) = fixture_zip();
+        let config = config(&["10", "R20"]);
+        let timetable = Timetable::load(&path, &config.stops).unwrap();
+        let context =
+            create_transit_context(&timetable, None, now("2025-05-05T08:00:00-07:00"), &config)
+                .unwrap();
+        assert_eq!(context.stops[0].name, "Main St & 3rd");
+        // T4 only runs on weekends, T5 runs on the added holiday service, T6 is route 30.
+        assert_eq!(
+            times(&context, 0),
+            vec![
+                ("08:05", "10", "T2"),
+                ("08:12", "20", "T5"),
+                ("08:20", "10", "T3")
+            ]
+        );
+        assert_eq!(context.stops[0].departures[0].minutes, 5);
+        // Only T2 serves S2, tomorrow's run follows today's.
+        assert_eq!(
+            context.stops[1]
+                .departures
+                .iter()
+                .map(|departure| departure.departure.as_str())
+                .collect::<Vec<_>>(),
+            vec!["2025-05-05T08:20:00-07:00", "2025-05-06T08:20:00-07:00"]
+        );
+    }
+
+    #[test]
+    fn it_should_include_trips_past_midnight_from_previous_service_day() {
+        let (_dir, path) = fixture_zip();
+        let config = config(&[]);
+        let timetable = Timetable::load(&path, &config.stops).unwrap();
+        let context =
+            create_transit_context(&timetable, None, now("2025-05-06T00:10:00-07:00"), &config)
+                .unwrap();
+        assert_eq!(times(&context, 0)[0], ("00:30", "10", "T8"));
+        assert_eq!(
+            context.stops[0].departures[0].departure,
+            "2025-05-06T00:30:00-07:00"
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_time_format() {
+        let (_dir, path) = fixture_zip();
+        let config = AppTransitConfig {
+            time_format: "%Q".to_string(),
+            ..config(&[])
+        };
+        let timetable = Timetable::load(&path, &config.stops).unwrap();
+        let error =
+            create_transit_context(&timetable, None, now("2025-05-05T08:00:00-07:00"), &config)
+                .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid time format %Q");
+    }
+
+    #[tokio::test]
+    async fn it_should_apply_realtime_trip_updates() {
+        let (dir, path) = fixture_zip();
+        let mut cancelled = trip_update("T3", vec![]);
+        if let Some(update) = &mut cancelled.trip_update {
+            update.trip.as_mut().unwrap().schedule_relationship = Some(TRIP_CANCELED);
+        }
+        let feed = FeedMessage {
+            header: Some(FeedHeader {
+                gtfs_realtime_version: Some("2.0".to_string()),
+                timestamp: Some(1746457200),
+            }),
+            entity: vec![
+                trip_update(
+                    "T2",
+                    vec![StopTimeUpdate {
+                        stop_sequence: Some(1),
+                        arrival: None,
+                        departure: Some(StopTimeEvent {
+                            delay: Some(300),
+                            time: None,
+                        }),
+                        stop_id: Some("S1".to_string()),
+                        schedule_relationship: None,
+                    }],
+                ),
+                cancelled,
+            ],
+        };
+        let realtime_path = dir.path().join("trip_updates.pb");
+        fs::write(&realtime_path, feed.encode_to_vec()).unwrap();
+
+        let config = config(&["10", "20"]);
+        let timetable = Timetable::load(&path, &config.stops).unwrap();
+        let feed = read_realtime(realtime_path.to_str().unwrap())
+            .await
+            .unwrap();
+        let context = create_transit_context(
+            &timetable,
+            Some(&feed),
+            now("2025-05-05T08:00:00-07:00"),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(
+            times(&context, 0),
+            vec![
+                ("08:10", "10", "T2"),
+                ("08:12", "20", "T5"),
+                ("08:35", "10", "T7")
+            ]
+        );
+        let delayed = &context.stops[0].departures[0];
+        assert!(delayed.realtime);
+        assert_eq!(delayed.delay_minutes, 5);
+        assert_eq!(delayed.scheduled, "2025-05-05T08:05:00-07:00");
+        // The delay carries over to later stops without their own update.
+        let later_stop = &context.stops[1].departures;
+        assert_eq!(later_stop[0].departure, "2025-05-05T08:25:00-07:00");
+        // The update is for today's run only.
+        assert_eq!(later_stop[1].departure, "2025-05-06T08:20:00-07:00");
+        assert!(!later_stop[1].realtime);
+    }
+
+    #[tokio::test]
+    async fn it_should_load_departures_for_configured_stops() {
+        let (_dir, path) = fixture_zip();
+        let mut registry = ContextRegistry::default();
+        registry.register(TransitContextProvider::default());
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.transit]
+                gtfs_path = "{}"
+                timezone = "America/Los_Angeles"
+                stops = ["S1"]
+                count = 2
+                "#,
+                path.display()
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["transit".to_string()])
+            .await
+            .unwrap();
+        // The test clock is Friday 2009-02-13 15:31 in Los Angeles.
+        assert_eq!(
+            contexts["transit"]["stops"][0]["departures"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|departure| departure["departure"].clone())
+                .collect::<Vec<_>>(),
+            vec![
+                json!("2009-02-14T00:30:00-08:00"),
+                json!("2009-02-14T08:10:00-08:00")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_should_fall_back_to_schedule_without_realtime_feed() {
+        let (_dir, path) = fixture_zip();
+        let mut registry = ContextRegistry::default();
+        registry.register(TransitContextProvider::default());
+        let (app_state, _config) = new_test_app_state(
+            &format!(
+                r#"
+                [default_context.transit]
+                gtfs_path = "{}"
+                realtime = "http://127.0.0.1:1/trip_updates.pb"
+                timezone = "America/Los_Angeles"
+                stops = ["S1"]
+                count = 1
+                "#,
+                path.display()
+            ),
+            registry,
+        );
+
+        let contexts = load_contexts(app_state, "fake_friendly_id", vec!["transit".to_string()])
+            .await
+            .unwrap();
+        let departure = &contexts["transit"]["stops"][0]["departures"][0];
+        assert_eq!(departure["departure"], json!("2009-02-14T00:30:00-08:00"));
+        assert_eq!(departure["realtime"], json!(false));
+    }
+
+    #[tokio::test]
+    async fn it_should_bound_timetable_cache() {
+        let (_dir, path) = fixture_zip();
+        let provider = TransitContextProvider::default();
+        for stop in 0..MAX_CACHED_TIMETABLES + 2 {
+            provider
+                .timetable(&path, &[format!("S{}", stop)])
+                .await
+                .unwrap();
+        }
+        let first = provider
+            .timetable(&path, &["S9".to_string()])
+            .await
+            .unwrap();
+        let cached = provider
+            .timetable(&path, &["S9".to_string()])
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &cached));
+        let cache = provider.cache.lock().unwrap();
+        assert_eq!(cache.len(), MAX_CACHED_TIMETABLES);
+        assert!(!cache.contains_key(&(path.clone(), vec!["S0".to_string()])));
+    }
+
+    #[test]
+    fn it_should_parse_times_after_midnight() {
+        assert_eq!(parse_gtfs_time("24:30:00").unwrap(), 88200);
+        assert_eq!(parse_gtfs_time(" 8:05:00").unwrap(), 29100);
+        assert!(parse_gtfs_time("08:05").is_err());
+    }
+}
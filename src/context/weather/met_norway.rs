@@ -0,0 +1,330 @@
+use crate::context::astronomy;
+use crate::context::weather::{
+    AppWeatherConfig, Forecast, HourWeather, WeatherIcon, WeatherProvider, apparent_temperature,
+    daily_from_hours, round,
+};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use reqwest::StatusCode;
+use reqwest::header::{EXPIRES, IF_MODIFIED_SINCE, LAST_MODIFIED};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const BASE_URL: &str = "https://api.met.no/weatherapi";
+
+/// The MET Norway locationforecast, which covers the whole world. Its terms of service ask
+/// clients to reuse a response until it `Expires` and to revalidate it with `If-Modified-Since`.
+pub struct MetNorway {
+    base_url: String,
+    responses: Mutex<HashMap<String, CachedResponse>>,
+}
+
+#[derive(Clone)]
+struct CachedResponse {
+    data: Arc<Value>,
+    expires: Option<DateTime<Utc>>,
+    last_modified: Option<String>,
+}
+
+impl Default for MetNorway {
+    fn default() -> MetNorway {
+        MetNorway {
+            base_url: BASE_URL.to_string(),
+            responses: Mutex::default(),
+        }
+    }
+}
+
+impl WeatherProvider for MetNorway {
+    async fn forecast(&self, config: &AppWeatherConfig, tz: Tz) -> anyhow::Result<Forecast> {
+        let (latitude, longitude) = config.coordinates()?;
+        let url = format!(
+            "{}/locationforecast/2.0/complete?lat={:.4}&lon={:.4}",
+            self.base_url, latitude, longitude
+        );
+        let data = self.get(&url, config).await?;
+        parse_forecast(&data, config, tz)
+    }
+}
+
+impl MetNorway {
+    async fn get(&self, url: &str, config: &AppWeatherConfig) -> anyhow::Result<Arc<Value>> {
+        let cached = self
+            .responses
+            .lock()
+            .map_err(|e| anyhow!("{}", e))?
+            .get(url)
+            .cloned();
+        if let Some(cached) = &cached
+            && cached.expires.is_some_and(|expires| Utc::now() < expires)
+        {
+            return Ok(cached.data.clone());
+        }
+        let mut request = reqwest::Client::new()
+            .get(url)
+            .header("User-Agent", &config.user_agent);
+        if let Some(last_modified) = cached
+            .as_ref()
+            .and_then(|cached| cached.last_modified.as_ref())
+        {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request.send().await?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let expires = header(EXPIRES)
+            .and_then(|expires| DateTime::parse_from_rfc2822(&expires).ok())
+            .map(|expires| expires.with_timezone(&Utc));
+        let last_modified = header(LAST_MODIFIED);
+        let response = match cached {
+            Some(cached) if response.status() == StatusCode::NOT_MODIFIED => CachedResponse {
+                expires,
+                last_modified: last_modified.or(cached.last_modified),
+                ..cached
+            },
+            _ => CachedResponse {
+                data: Arc::new(response.error_for_status()?.json().await?),
+                expires,
+                last_modified,
+            },
+        };
+        let data = response.data.clone();
+        self.responses
+            .lock()
+            .map_err(|e| anyhow!("{}", e))?
+            .insert(url.to_string(), response);
+        Ok(data)
+    }
+}
+
+/// Entries are hourly for the first days and 6 hourly after, at UTC times and in metric units.
+fn parse_forecast(data: &Value, config: &AppWeatherConfig, tz: Tz) -> anyhow::Result<Forecast> {
+    let entries = data["properties"]["timeseries"]
+        .as_array()
+        .context("Missing properties.timeseries in forecast")?;
+    let (latitude, longitude) = config.coordinates()?;
+    let mut current = None;
+    let mut hours = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let (time, hour) = parse_entry(entry, config, tz, (latitude, longitude))
+            .context(format!("Invalid forecast entry {}", index))?;
+        current.get_or_insert(hour);
+        hours.entry(time).or_insert(hour);
+    }
+    Ok(Forecast {
+        current: current.context("Empty forecast")?,
+        days: daily_from_hours(&hours, config, tz)?,
+        hours,
+    })
+}
+
+fn parse_entry(
+    entry: &Value,
+    config: &AppWeatherConfig,
+    tz: Tz,
+    (latitude, longitude): (f64, f64),
+) -> anyhow::Result<(NaiveDateTime, HourWeather)> {
+    let time: DateTime<Utc> = entry["time"].as_str().context("Missing time")?.parse()?;
+    let details = &entry["data"]["instant"]["details"];
+    let celsius = details["air_temperature"]
+        .as_f64()
+        .context("Missing air_temperature")?;
+    let humidity = details["relative_humidity"].as_f64().unwrap_or_default();
+    let wind_speed = details["wind_speed"].as_f64().unwrap_or_default();
+    // The summary of the following hour, or of the following 6 hours further out
+    let next = ["next_1_hours", "next_6_hours"]
+        .iter()
+        .map(|period| &entry["data"][period])
+        .find(|next| next.is_object())
+        .unwrap_or(&Value::Null);
+    let symbol = next["summary"]["symbol_code"].as_str().unwrap_or_default();
+    let (condition, variant) = symbol.split_once('_').unwrap_or((symbol, ""));
+    let is_day = match variant {
+        "day" => true,
+        "night" => false,
+        _ => astronomy::is_sun_up(time, latitude, longitude),
+    };
+    let temperature_unit = config.temperature_unit();
+    let local = time.with_timezone(&tz).naive_local();
+    Ok((
+        local,
+        HourWeather {
+            icon: icon(condition, is_day),
+            temperature: round(temperature_unit.convert_celsius(celsius)),
+            hour: local.hour() as usize,
+            precipitation_probability: next["details"]["probability_of_precipitation"]
+                .as_f64()
+                .unwrap_or_default(),
+            apparent_temperature: round(
+                temperature_unit
+                    .convert_celsius(apparent_temperature(celsius, humidity, wind_speed)),
+            ),
+            humidity,
+            wind_speed: round(
+                config
+                    .wind_speed_unit()
+                    .convert_meters_per_second(wind_speed),
+            ),
+            wind_direction: details["wind_from_direction"].as_f64().unwrap_or_default(),
+            uv_index: details["ultraviolet_index_clear_sky"].as_f64(),
+            is_day,
+        },
+    ))
+}
+
+/// Symbol codes without their `_day`, `_night` or `_polartwilight` suffix.
+fn icon(condition: &str, is_day: bool) -> WeatherIcon {
+    match condition {
+        condition if condition.contains("thunder") => WeatherIcon::Thunderstorm,
+        "clearsky" if is_day => WeatherIcon::Sunny,
+        "clearsky" => WeatherIcon::ClearNight,
+        "fair" | "partlycloudy" if is_day => WeatherIcon::PartlyCloudyDay,
+        "fair" | "partlycloudy" => WeatherIcon::PartlyCloudyNight,
+        "cloudy" => WeatherIcon::Cloudy,
+        "fog" => WeatherIcon::Foggy,
+        "lightrain" | "lightrainshowers" => WeatherIcon::RainyLight,
+        "rain" | "rainshowers" => WeatherIcon::Rainy,
+        "heavyrain" | "heavyrainshowers" => WeatherIcon::RainyHeavy,
+        "lightsleet" | "lightsleetshowers" | "sleet" | "sleetshowers" => WeatherIcon::AcUnit,
+        "heavysleet" | "heavysleetshowers" => WeatherIcon::SevereCold,
+        "lightsnow" | "lightsnowshowers" => WeatherIcon::WeatherSnowy,
+        "snow" | "snowshowers" => WeatherIcon::Snowing,
+        "heavysnow" | "heavysnowshowers" => WeatherIcon::SnowingHeavy,
+        _ => WeatherIcon::Help,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::serve;
+    use crate::context::weather::WeatherIcon::{
+        ClearNight, Cloudy, PartlyCloudyDay, RainyLight, Sunny, Thunderstorm,
+    };
+    use crate::context::weather::tests::new_config;
+    use crate::context::weather::{
+        AppWeatherConfig, WeatherDayMode, WeatherUnitSystem, parse_weather_data,
+    };
+    use axum::Router;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use chrono_tz::Europe::Oslo;
+
+    fn new_oslo_config() -> AppWeatherConfig {
+        AppWeatherConfig {
+            latitude: "59.9139".to_string(),
+            longitude: "10.7522".to_string(),
+            timezone: "Europe/Oslo".to_string(),
+            units: WeatherUnitSystem::Metric,
+            hours: vec![2, 8, 14],
+            days: 5,
+            day_mode: WeatherDayMode::NextDays,
+            ..new_config()
+        }
+    }
+
+    #[test]
+    fn it_should_parse_sample_forecast() {
+        let data = serde_json::from_str(include_str!("sample_met_norway_forecast.json")).unwrap();
+        let config = new_oslo_config();
+        let forecast = parse_forecast(&data, &config, Oslo).unwrap();
+        assert_eq!(forecast.hours.len(), 73);
+        // 12:00 UTC is 14:00 in Oslo, with a 3 m/s wind.
+        assert_eq!(forecast.current.hour, 14);
+        assert_eq!(forecast.current.temperature, 18.8);
+        assert_eq!(forecast.current.wind_speed, 10.8);
+        assert_eq!(forecast.current.uv_index, Some(5.0));
+        assert_eq!(forecast.current.apparent_temperature, 16.0);
+        assert_eq!(forecast.current.icon, Sunny);
+
+        let now = "2025-05-24T12:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let result = parse_weather_data(now.with_timezone(&Oslo), &forecast, &config).unwrap();
+        let today = &result.days[0];
+        assert_eq!(today.hours[..2], [None, None]);
+        assert_eq!(today.hours[2].unwrap().temperature, 18.8);
+        let tomorrow = &result.days[1];
+        assert_eq!(tomorrow.hours[0].unwrap().icon, ClearNight);
+        assert_eq!(tomorrow.hours[1].unwrap().icon, PartlyCloudyDay);
+        assert_eq!(tomorrow.hours[2].unwrap().icon, RainyLight);
+        assert_eq!(tomorrow.hours[2].unwrap().precipitation_probability, 45.0);
+        assert_eq!(tomorrow.sunrise.as_deref(), Some("04:18"));
+        assert_eq!(tomorrow.sunset.as_deref(), Some("22:10"));
+        // Symbols without a day or night variant fall back to the position of the sun.
+        let monday = &result.days[2];
+        assert_eq!(monday.hours[0].unwrap().icon, Cloudy);
+        assert!(!monday.hours[0].unwrap().is_day);
+        assert_eq!(monday.hours[2].unwrap().icon, Sunny);
+        // Only 6 hourly entries are left on Wednesday, at 02:00, 08:00, 14:00 and 20:00.
+        let wednesday = &result.days[4];
+        assert_eq!(wednesday.hours[1].unwrap().temperature, 11.6);
+        assert!(wednesday.hours.iter().all(Option::is_some));
+        assert!(result.days[4].temperature_max.is_some());
+    }
+
+    #[tokio::test]
+    async fn it_should_reuse_responses_until_they_expire() {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let seen = requests.clone();
+        let url = serve(Router::new().route(
+            "/locationforecast/2.0/complete",
+            get(move |headers: HeaderMap| {
+                let mut seen = seen.lock().unwrap();
+                let if_modified_since = headers
+                    .get(IF_MODIFIED_SINCE)
+                    .map(|value| value.to_str().unwrap().to_string());
+                seen.push(if_modified_since.clone());
+                async move {
+                    match if_modified_since {
+                        // Revalidated responses stay fresh for long
+                        Some(_) => (
+                            StatusCode::NOT_MODIFIED,
+                            [
+                                (EXPIRES, "Fri, 01 Jan 2100 00:00:00 GMT"),
+                                (LAST_MODIFIED, "Sat, 24 May 2025 12:00:00 GMT"),
+                            ],
+                            "",
+                        ),
+                        None => (
+                            StatusCode::OK,
+                            [
+                                (EXPIRES, "Thu, 01 Jan 2009 00:00:00 GMT"),
+                                (LAST_MODIFIED, "Sat, 24 May 2025 12:00:00 GMT"),
+                            ],
+                            include_str!("sample_met_norway_forecast.json"),
+                        ),
+                    }
+                }
+            }),
+        ))
+        .await;
+        let provider = MetNorway {
+            base_url: url,
+            ..MetNorway::default()
+        };
+        let config = new_oslo_config();
+        for _ in 0..3 {
+            let forecast = provider.forecast(&config, Oslo).await.unwrap();
+            assert_eq!(forecast.hours.len(), 73);
+        }
+        // The first response has already expired, the revalidated one has not.
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![None, Some("Sat, 24 May 2025 12:00:00 GMT".to_string())]
+        );
+    }
+
+    #[test]
+    fn it_should_convert_symbols_to_icons() {
+        assert_eq!(icon("rainandthunder", true), Thunderstorm);
+        assert_eq!(icon("clearsky", false), ClearNight);
+        assert_eq!(icon("cloudy", true), Cloudy);
+        assert_eq!(icon("unknown", true), WeatherIcon::Help);
+    }
+}
@@ -1,27 +1,184 @@
-use crate::context::ContextConfig;
-use anyhow::Context;
-use chrono::Weekday::{Fri, Mon, Sat, Sun};
-use chrono::{DateTime, Datelike, Utc};
+mod met_norway;
+mod nws;
+mod open_meteo;
+
+use crate::api::AppState;
+use crate::context::astronomy;
+use crate::context::time::parse_timezone;
+use crate::context::weather::met_norway::MetNorway;
+use crate::context::weather::nws::Nws;
+use crate::context::weather::open_meteo::OpenMeteo;
+use crate::context::{ContextConfig, ContextProvider};
+use anyhow::anyhow;
+use chrono::Weekday::{Sat, Sun};
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, Utc};
 use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::ops::Add;
-use std::str::FromStr;
+use std::collections::HashMap;
 use tracing::info;
-use url::Url;
-
-const HOUR_COUNT: usize = 5;
-const DAY_COUNT: usize = 4;
-const TODAY_OFFSET: usize = 0;
-const TOMORROW_OFFSET: usize = 24;
-const NEXT_WEEK_OFFSET: usize = 24 * 7;
-const HOURS: HourIndexes<HOUR_COUNT> = HourIndexes([7, 10, 12, 15, 18]);
 
 #[derive(Debug, Deserialize)]
 pub struct AppWeatherConfig {
+    #[serde(default)]
+    pub provider: WeatherProviderKind,
+    /// The National Weather Service and MET Norway reject requests without an identifying agent.
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
     pub latitude: String,
     pub longitude: String,
     pub timezone: String,
+    #[serde(default)]
+    pub units: WeatherUnitSystem,
+    /// Overrides the unit from `units`.
+    pub temperature_unit: Option<TemperatureUnit>,
+    pub wind_speed_unit: Option<WindSpeedUnit>,
+    pub precipitation_unit: Option<PrecipitationUnit>,
+    /// Hours of the day sampled for each forecast day.
+    #[serde(default = "default_hours")]
+    pub hours: Vec<usize>,
+    #[serde(default = "default_days")]
+    pub days: usize,
+    #[serde(default)]
+    pub day_mode: WeatherDayMode,
+}
+
+fn default_user_agent() -> String {
+    "svg-trmnl-server (https://github.com/bayne/svg-trmnl-server)".to_string()
+}
+
+fn default_hours() -> Vec<usize> {
+    vec![7, 10, 12, 15, 18]
+}
+
+fn default_days() -> usize {
+    4
+}
+
+#[derive(Debug, Default, Copy, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherProviderKind {
+    #[default]
+    OpenMeteo,
+    /// The US National Weather Service, only covers the United States.
+    Nws,
+    MetNorway,
+}
+
+/// Which days follow today and tomorrow.
+#[derive(Debug, Default, Copy, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherDayMode {
+    NextDays,
+    /// The next Saturday and Sunday after tomorrow, then the days after them.
+    #[default]
+    Weekend,
+}
+
+#[derive(Debug, Default, Copy, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeatherUnitSystem {
+    Metric,
+    #[default]
+    Imperial,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WindSpeedUnit {
+    Kmh,
+    Ms,
+    Mph,
+    Kn,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PrecipitationUnit {
+    Mm,
+    Inch,
+}
+
+impl TemperatureUnit {
+    fn convert_celsius(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+}
+
+impl WindSpeedUnit {
+    fn convert_meters_per_second(self, speed: f64) -> f64 {
+        match self {
+            WindSpeedUnit::Kmh => speed * 3.6,
+            WindSpeedUnit::Ms => speed,
+            WindSpeedUnit::Mph => speed / 0.44704,
+            WindSpeedUnit::Kn => speed * 3600.0 / 1852.0,
+        }
+    }
+}
+
+impl AppWeatherConfig {
+    fn coordinates(&self) -> anyhow::Result<(f64, f64)> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| anyhow!("Invalid coordinate {}: {}", value, e))
+        };
+        Ok((parse(&self.latitude)?, parse(&self.longitude)?))
+    }
+
+    fn temperature_unit(&self) -> TemperatureUnit {
+        self.temperature_unit.unwrap_or(match self.units {
+            WeatherUnitSystem::Metric => TemperatureUnit::Celsius,
+            WeatherUnitSystem::Imperial => TemperatureUnit::Fahrenheit,
+        })
+    }
+
+    fn wind_speed_unit(&self) -> WindSpeedUnit {
+        self.wind_speed_unit.unwrap_or(match self.units {
+            WeatherUnitSystem::Metric => WindSpeedUnit::Kmh,
+            WeatherUnitSystem::Imperial => WindSpeedUnit::Mph,
+        })
+    }
+
+    fn precipitation_unit(&self) -> PrecipitationUnit {
+        self.precipitation_unit.unwrap_or(match self.units {
+            WeatherUnitSystem::Metric => PrecipitationUnit::Mm,
+            WeatherUnitSystem::Imperial => PrecipitationUnit::Inch,
+        })
+    }
+
+    fn unit_symbols(&self) -> WeatherUnits {
+        WeatherUnits {
+            temperature: match self.temperature_unit() {
+                TemperatureUnit::Celsius => "°C",
+                TemperatureUnit::Fahrenheit => "°F",
+            }
+            .to_string(),
+            wind_speed: match self.wind_speed_unit() {
+                WindSpeedUnit::Kmh => "km/h",
+                WindSpeedUnit::Ms => "m/s",
+                WindSpeedUnit::Mph => "mph",
+                WindSpeedUnit::Kn => "kn",
+            }
+            .to_string(),
+            precipitation: match self.precipitation_unit() {
+                PrecipitationUnit::Mm => "mm",
+                PrecipitationUnit::Inch => "in",
+            }
+            .to_string(),
+        }
+    }
 }
 
 impl ContextConfig for AppWeatherConfig {
@@ -34,13 +191,27 @@ impl ContextConfig for AppWeatherConfig {
 pub struct WeatherContext {
     pub current: HourWeather,
     pub time: String,
-    pub days: [DayWeather; DAY_COUNT],
+    pub days: Vec<DayWeather>,
+    pub units: WeatherUnits,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct WeatherUnits {
+    pub temperature: String,
+    pub wind_speed: String,
+    pub precipitation: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct DayWeather {
     pub title: String,
-    pub hours: [Option<HourWeather>; HOUR_COUNT],
+    pub hours: Vec<Option<HourWeather>>,
+    pub temperature_max: Option<f64>,
+    pub temperature_min: Option<f64>,
+    pub precipitation_probability_max: Option<f64>,
+    pub uv_index_max: Option<f64>,
+    pub sunrise: Option<String>,
+    pub sunset: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
@@ -49,14 +220,26 @@ pub struct HourWeather {
     pub temperature: f64,
     pub hour: usize,
     pub precipitation_probability: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub wind_speed: f64,
+    /// Degrees the wind is coming from, 0 is north.
+    pub wind_direction: f64,
+    /// Not every provider forecasts the UV index.
+    pub uv_index: Option<f64>,
+    pub is_day: bool,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
 pub enum WeatherIcon {
     #[serde(rename = "sunny")]
     Sunny,
+    #[serde(rename = "bedtime")]
+    ClearNight,
     #[serde(rename = "partly_cloudy_day")]
     PartlyCloudyDay,
+    #[serde(rename = "partly_cloudy_night")]
+    PartlyCloudyNight,
     #[serde(rename = "cloud")]
     Cloud,
     #[serde(rename = "cloudy")]
@@ -87,212 +270,474 @@ pub enum WeatherIcon {
     Help,
 }
 
-pub async fn create_weather_context(config: &AppWeatherConfig) -> anyhow::Result<WeatherContext> {
-    info!(
-        "Getting weather data for location, with config {:?}",
-        config
-    );
-    let result = get_weather(config).await?;
-    let tz: Tz = config.timezone.parse()?;
-    let now = Utc::now().with_timezone(&tz);
-    parse_weather_data(now, &result)
-}
-
-type HourIndex = usize;
-struct HourIndexes<const N: usize>([HourIndex; N]);
-impl<const N: usize> Add for HourIndexes<N> {
-    type Output = HourIndexes<N>;
-    fn add(self, HourIndexes(other): HourIndexes<N>) -> HourIndexes<N> {
-        let mut result = [0; N];
-        for i in 0..N {
-            result[i] = self.0[i] + other[i];
-        }
-        HourIndexes(result)
-    }
+/// A provider's forecast in the configured units, keyed by local time in the configured timezone.
+pub struct Forecast {
+    pub current: HourWeather,
+    pub hours: HashMap<NaiveDateTime, HourWeather>,
+    pub days: HashMap<NaiveDate, DailyWeather>,
 }
 
-impl HourIndexes<HOUR_COUNT> {
-    fn new(offset: usize) -> Self {
-        HOURS + HourIndexes([offset; HOUR_COUNT])
-    }
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DailyWeather {
+    pub temperature_max: Option<f64>,
+    pub temperature_min: Option<f64>,
+    pub precipitation_probability_max: Option<f64>,
+    pub uv_index_max: Option<f64>,
+    pub sunrise: Option<String>,
+    pub sunset: Option<String>,
 }
 
-impl HourWeather {
-    fn extract<T>(
-        data: &Value,
-        hour_index: HourIndex,
-        field: &'static str,
-        mapper: impl Fn(&Value) -> Option<T>,
-    ) -> anyhow::Result<T> {
-        let value = &data["hourly"][field][hour_index];
-        Ok(mapper(value).context(format!(
-            "field error hourly.{}[{}]: {:?}",
-            field, hour_index, value
-        ))?)
-    }
+pub trait WeatherProvider {
+    fn forecast(
+        &self,
+        config: &AppWeatherConfig,
+        tz: Tz,
+    ) -> impl Future<Output = anyhow::Result<Forecast>> + Send;
+}
 
-    fn get<T>(
-        data: &Value,
-        field: &'static str,
-        mapper: impl Fn(&Value) -> Option<T>,
-    ) -> anyhow::Result<T> {
-        let value = &data["current"][field];
-        Ok(mapper(value).context(format!("field error current.[{}]: {:?}", field, value))?)
-    }
+/// Keeps the providers' cached lookups and responses between renders.
+#[derive(Default)]
+pub struct WeatherContextProvider {
+    nws: Nws,
+    met_norway: MetNorway,
+}
 
-    fn current(data: &Value) -> anyhow::Result<HourWeather> {
-        Ok(HourWeather {
-            icon: {
-                let weather_code = Self::get(data, "weather_code", |v| v.as_u64())?;
-                weather_code_to_icon(weather_code)
-            },
-            temperature: Self::get(data, "temperature_2m", |v| v.as_f64())?,
-            hour: 0,
-            precipitation_probability: 0.0,
-        })
+impl ContextProvider for WeatherContextProvider {
+    type Config = AppWeatherConfig;
+
+    async fn fetch(
+        &self,
+        app_state: &AppState,
+        _friendly_id: &str,
+        config: AppWeatherConfig,
+    ) -> anyhow::Result<Value> {
+        let now = DateTime::<Utc>::from(app_state.clock.now());
+        Ok(serde_json::to_value(
+            self.create_weather_context(now, &config).await?,
+        )?)
     }
+}
 
-    fn from(data: &Value, hour_index: HourIndex) -> anyhow::Result<HourWeather> {
-        Ok(HourWeather {
-            icon: {
-                let weather_code = Self::extract(data, hour_index, "weather_code", |v| v.as_u64())?;
-                weather_code_to_icon(weather_code)
-            },
-            temperature: Self::extract(data, hour_index, "temperature_2m", |v| v.as_f64())?,
-            hour: hour_index % 24,
-            precipitation_probability: Self::extract(
-                data,
-                hour_index,
-                "precipitation_probability",
-                |v| v.as_f64(),
-            )?,
-        })
+impl WeatherContextProvider {
+    pub async fn create_weather_context(
+        &self,
+        now: DateTime<Utc>,
+        config: &AppWeatherConfig,
+    ) -> anyhow::Result<WeatherContext> {
+        info!(
+            "Getting weather data for location, with config {:?}",
+            config
+        );
+        let tz = parse_timezone(&config.timezone)?;
+        let forecast = match config.provider {
+            WeatherProviderKind::OpenMeteo => OpenMeteo.forecast(config, tz).await?,
+            WeatherProviderKind::Nws => self.nws.forecast(config, tz).await?,
+            WeatherProviderKind::MetNorway => self.met_norway.forecast(config, tz).await?,
+        };
+        parse_weather_data(now.with_timezone(&tz), &forecast, config)
     }
 }
 
 impl DayWeather {
-    fn from(
-        value: &Value,
-        title: &str,
-        HourIndexes(hour_indexes): HourIndexes<HOUR_COUNT>,
-    ) -> anyhow::Result<DayWeather> {
-        let mut hours: [Option<HourWeather>; HOUR_COUNT] = [None; HOUR_COUNT];
-        for i in 0..HOUR_COUNT {
-            hours[i] = Some(HourWeather::from(value, hour_indexes[i])?);
-        }
-        Ok(DayWeather {
-            title: title.to_string(),
+    fn from(forecast: &Forecast, title: String, date: NaiveDate, hours: &[usize]) -> DayWeather {
+        let hours = hours
+            .iter()
+            .map(|hour| {
+                let time = date.and_hms_opt(*hour as u32, 0, 0)?;
+                forecast.hours.get(&time).copied()
+            })
+            .collect();
+        let daily = forecast.days.get(&date).cloned().unwrap_or_default();
+        DayWeather {
+            title,
             hours,
-        })
+            temperature_max: daily.temperature_max,
+            temperature_min: daily.temperature_min,
+            precipitation_probability_max: daily.precipitation_probability_max,
+            uv_index_max: daily.uv_index_max,
+            sunrise: daily.sunrise,
+            sunset: daily.sunset,
+        }
     }
 }
 
-fn weather_code_to_icon(code: u64) -> WeatherIcon {
-    match code {
-        0 => WeatherIcon::Sunny,
-        1 | 2 => WeatherIcon::PartlyCloudyDay,
-        3 => WeatherIcon::Cloudy,
-        45 | 48 => WeatherIcon::Foggy,
-        51 | 61 | 80 => WeatherIcon::RainyLight,
-        53 | 63 | 81 => WeatherIcon::Rainy,
-        55 | 65 | 82 => WeatherIcon::RainyHeavy,
-        56 | 66 => WeatherIcon::AcUnit,
-        57 | 67 => WeatherIcon::SevereCold,
-        71 | 85 => WeatherIcon::WeatherSnowy,
-        73 => WeatherIcon::Snowing,
-        75 | 86 => WeatherIcon::SnowingHeavy,
-        77 => WeatherIcon::Grain,
-        95 | 96 | 99 => WeatherIcon::Thunderstorm,
-        _ => WeatherIcon::Help,
+/// Rounded to one decimal like Open-Meteo's values.
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// The Australian Bureau of Meteorology's apparent temperature, for providers that don't forecast
+/// one.
+fn apparent_temperature(celsius: f64, humidity: f64, wind_speed: f64) -> f64 {
+    let vapour_pressure = humidity / 100.0 * 6.105 * (17.27 * celsius / (237.7 + celsius)).exp();
+    celsius + 0.33 * vapour_pressure - 0.70 * wind_speed - 4.0
+}
+
+/// Daily values for providers that only forecast hours, today only covers the hours that are left.
+fn daily_from_hours(
+    hours: &HashMap<NaiveDateTime, HourWeather>,
+    config: &AppWeatherConfig,
+    tz: Tz,
+) -> anyhow::Result<HashMap<NaiveDate, DailyWeather>> {
+    let (latitude, longitude) = config.coordinates()?;
+    let mut days: HashMap<NaiveDate, Vec<&HourWeather>> = HashMap::new();
+    for (time, hour) in hours {
+        days.entry(time.date()).or_default().push(hour);
     }
+    days.into_iter()
+        .map(|(date, hours)| {
+            let max = |value: fn(&HourWeather) -> Option<f64>| {
+                hours.iter().filter_map(|hour| value(hour)).reduce(f64::max)
+            };
+            let (sunrise, sunset) = astronomy::sunrise_sunset(date, latitude, longitude)?;
+            let local = |time: Option<DateTime<Utc>>| {
+                time.map(|time| time.with_timezone(&tz).format("%H:%M").to_string())
+            };
+            Ok((
+                date,
+                DailyWeather {
+                    temperature_max: max(|hour| Some(hour.temperature)),
+                    temperature_min: hours.iter().map(|hour| hour.temperature).reduce(f64::min),
+                    precipitation_probability_max: max(|hour| Some(hour.precipitation_probability)),
+                    uv_index_max: max(|hour| hour.uv_index),
+                    sunrise: local(sunrise),
+                    sunset: local(sunset),
+                },
+            ))
+        })
+        .collect()
 }
 
-async fn get_weather(config: &AppWeatherConfig) -> anyhow::Result<Value> {
-    let mut url = Url::from_str("https://api.open-meteo.com")?;
-    url.set_path("/v1/forecast");
-    url.query_pairs_mut()
-        .append_pair("latitude", &config.latitude)
-        .append_pair("longitude", &config.longitude)
-        .append_pair(
-            "hourly",
-            "temperature_2m,rain,precipitation_probability,weather_code",
-        )
-        .append_pair(
-            "current",
-            "temperature_2m,rain,precipitation_probability,weather_code",
-        )
-        .append_pair("timezone", &config.timezone)
-        .append_pair("wind_speed_unit", "mph")
-        .append_pair("temperature_unit", "fahrenheit")
-        .append_pair("forecast_days", "14")
-        .append_pair("precipitation_unit", "inch");
-
-    let result = reqwest::get(url).await?.json().await?;
-    Ok(result)
-}
-
-fn parse_weather_data(now: DateTime<Tz>, data: &Value) -> anyhow::Result<WeatherContext> {
-    let weekday = now.weekday();
-    let (third_offset, fourth_offset) = {
-        let (third_offset, fourth_offset) = match weekday {
-            Fri => (Sun.days_since(weekday), Mon.days_since(weekday)),
-            Sat | Sun => (
-                NEXT_WEEK_OFFSET as u32 + Sat.days_since(weekday),
-                NEXT_WEEK_OFFSET as u32 + Sun.days_since(weekday),
-            ),
-            _ => (Sat.days_since(weekday), Sun.days_since(weekday)),
-        };
-        (third_offset as usize, fourth_offset as usize)
+/// Day offsets from today with their titles, today and tomorrow always come first.
+fn select_days(now: DateTime<Tz>, count: usize, mode: WeatherDayMode) -> Vec<(usize, String)> {
+    let today = now.date_naive();
+    let weekday = today.weekday();
+    let first_later_day = match mode {
+        WeatherDayMode::NextDays => 2,
+        WeatherDayMode::Weekend => (2..)
+            .find(|offset| matches!((today + Days::new(*offset)).weekday(), Sat | Sun))
+            .unwrap_or(2) as usize,
     };
-    let (third_title, fourth_title) = match weekday {
-        Fri => ("Sun", "Mon"),
-        Sat | Sun => ("Next Sat", "Next Sun"),
-        _ => ("Sat", "Sun"),
+    let prefix = match (mode, weekday) {
+        (WeatherDayMode::Weekend, Sat | Sun) => "Next ",
+        _ => "",
     };
+    (0..count)
+        .map(|index| match index {
+            0 => (0, "Today".to_string()),
+            1 => (1, "Tomorrow".to_string()),
+            _ => {
+                let offset = first_later_day + index - 2;
+                let day = today + Days::new(offset as u64);
+                (offset, format!("{}{}", prefix, day.format("%a")))
+            }
+        })
+        .collect()
+}
+
+fn parse_weather_data(
+    now: DateTime<Tz>,
+    forecast: &Forecast,
+    config: &AppWeatherConfig,
+) -> anyhow::Result<WeatherContext> {
+    if let Some(hour) = config.hours.iter().find(|hour| **hour > 23) {
+        return Err(anyhow!("Invalid forecast hour {}", hour));
+    }
+    let days = select_days(now, config.days, config.day_mode)
+        .into_iter()
+        .map(|(offset, title)| {
+            let date = now.date_naive() + Days::new(offset as u64);
+            DayWeather::from(forecast, title, date, &config.hours)
+        })
+        .collect();
 
     Ok(WeatherContext {
-        current: HourWeather::current(data)?,
+        current: forecast.current,
         time: now.format("%a, %b-%e %l%P").to_string(),
-        days: [
-            DayWeather::from(data, "Today", HourIndexes::new(TODAY_OFFSET))?,
-            DayWeather::from(data, "Tomorrow", HourIndexes::new(TOMORROW_OFFSET))?,
-            DayWeather::from(data, third_title, HourIndexes::new(third_offset * 24))?,
-            DayWeather::from(data, fourth_title, HourIndexes::new(fourth_offset * 24))?,
-        ],
+        days,
+        units: config.unit_symbols(),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::context::weather::WeatherIcon::Sunny;
+    use crate::context::weather::WeatherIcon::{ClearNight, PartlyCloudyNight, Sunny};
     use chrono_tz::Etc::UTC;
 
+    fn parse_open_meteo(
+        now: DateTime<Tz>,
+        data: &Value,
+        config: &AppWeatherConfig,
+    ) -> anyhow::Result<WeatherContext> {
+        parse_weather_data(now, &open_meteo::parse_forecast(data)?, config)
+    }
+
     #[test]
     fn it_should_parse_sample_json() {
         let json = include_str!("sample_forecast.json");
         let result = serde_json::from_str::<Value>(json).unwrap();
         let now = Utc::now().with_timezone(&UTC);
-        let result = parse_weather_data(now, &result).unwrap();
+        let result = parse_open_meteo(now, &result, &new_config()).unwrap();
         assert!(serde_json::to_string_pretty(&result).is_ok())
     }
 
+    pub(super) fn at(time: &str) -> DateTime<Tz> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&chrono_tz::America::Los_Angeles)
+    }
+
+    #[test]
+    fn it_should_parse_daily_and_current_fields() {
+        let data = serde_json::from_str::<Value>(include_str!("sample_forecast.json")).unwrap();
+        let result =
+            parse_open_meteo(at("2025-05-24T14:30:00-07:00"), &data, &new_config()).unwrap();
+        assert_eq!(result.current.temperature, 72.0);
+        assert_eq!(result.current.apparent_temperature, 70.6);
+        assert_eq!(result.current.humidity, 51.0);
+        assert_eq!(result.current.wind_speed, 5.9);
+        assert_eq!(result.current.wind_direction, 315.0);
+        assert_eq!(result.current.uv_index, Some(6.85));
+
+        let today = &result.days[0];
+        assert_eq!(today.temperature_max, Some(75.6));
+        assert_eq!(today.temperature_min, Some(48.1));
+        assert_eq!(today.uv_index_max, Some(7.8));
+        assert_eq!(today.sunrise.as_deref(), Some("05:33"));
+        assert_eq!(today.sunset.as_deref(), Some("20:45"));
+        let afternoon = today.hours[3].unwrap();
+        assert_eq!(afternoon.hour, 15);
+        assert!(afternoon.humidity > 0.0 && afternoon.wind_speed > 0.0);
+    }
+
+    #[test]
+    fn it_should_select_weekend_days() {
+        let expected = |days: &[(usize, &str)]| {
+            days.iter()
+                .map(|(offset, title)| (*offset, title.to_string()))
+                .collect::<Vec<_>>()
+        };
+        // 2025-05-21 is a Wednesday.
+        assert_eq!(
+            select_days(at("2025-05-21T09:00:00-07:00"), 4, WeatherDayMode::Weekend),
+            expected(&[(0, "Today"), (1, "Tomorrow"), (3, "Sat"), (4, "Sun")])
+        );
+        assert_eq!(
+            select_days(at("2025-05-23T09:00:00-07:00"), 4, WeatherDayMode::Weekend),
+            expected(&[(0, "Today"), (1, "Tomorrow"), (2, "Sun"), (3, "Mon")])
+        );
+        assert_eq!(
+            select_days(at("2025-05-24T09:00:00-07:00"), 4, WeatherDayMode::Weekend),
+            expected(&[
+                (0, "Today"),
+                (1, "Tomorrow"),
+                (7, "Next Sat"),
+                (8, "Next Sun")
+            ])
+        );
+        assert_eq!(
+            select_days(at("2025-05-25T09:00:00-07:00"), 4, WeatherDayMode::Weekend),
+            expected(&[
+                (0, "Today"),
+                (1, "Tomorrow"),
+                (6, "Next Sat"),
+                (7, "Next Sun")
+            ])
+        );
+        assert_eq!(
+            select_days(at("2025-05-24T09:00:00-07:00"), 5, WeatherDayMode::NextDays),
+            expected(&[
+                (0, "Today"),
+                (1, "Tomorrow"),
+                (2, "Mon"),
+                (3, "Tue"),
+                (4, "Wed")
+            ])
+        );
+    }
+
     #[test]
-    fn it_should_convert_weather_code_to_icon() {
-        assert_eq!(weather_code_to_icon(0), Sunny);
+    fn it_should_sample_configured_hours_and_days() {
+        let data = serde_json::from_str::<Value>(include_str!("sample_forecast.json")).unwrap();
+        let config = AppWeatherConfig {
+            hours: vec![6, 21],
+            days: 3,
+            day_mode: WeatherDayMode::NextDays,
+            ..new_config()
+        };
+        // The sample forecast starts on Saturday 2025-05-24.
+        let result = parse_open_meteo(at("2025-05-24T14:30:00-07:00"), &data, &config).unwrap();
+        assert_eq!(result.days.len(), 3);
+        assert_eq!(result.days[2].title, "Mon");
+        assert_eq!(result.days[2].hours.len(), 2);
+        assert_eq!(result.days[2].hours[1].unwrap().hour, 21);
+        assert_eq!(result.days[2].temperature_max, Some(68.7));
+        assert_eq!(result.days[2].temperature_min, Some(52.8));
+
+        let result =
+            parse_open_meteo(at("2025-05-24T14:30:00-07:00"), &data, &new_config()).unwrap();
+        assert_eq!(result.days[3].title, "Next Sun");
+
+        let config = AppWeatherConfig {
+            hours: vec![24],
+            ..new_config()
+        };
+        assert!(parse_open_meteo(at("2025-05-24T14:30:00-07:00"), &data, &config).is_err());
     }
 
-    #[ignore = "requires internet connection"]
-    #[tokio::test]
-    async fn it_should_get_weather() {
+    /// An hourly forecast in Los Angeles time, each temperature is the hour's array position.
+    fn new_dst_forecast(start: &str, hours: usize) -> Value {
+        let start = DateTime::parse_from_rfc3339(start)
+            .unwrap()
+            .with_timezone(&Utc);
+        let times: Vec<DateTime<Tz>> = (0..hours)
+            .map(|hour| {
+                (start + chrono::Duration::hours(hour as i64))
+                    .with_timezone(&chrono_tz::America::Los_Angeles)
+            })
+            .collect();
+        let mut dates: Vec<String> = times
+            .iter()
+            .map(|time| time.format("%Y-%m-%d").to_string())
+            .collect();
+        dates.dedup();
+        let repeat = |value: Value| vec![value; hours];
+        serde_json::json!({
+            "current": {
+                "temperature_2m": 50.0, "weather_code": 3, "apparent_temperature": 48.0,
+                "relative_humidity_2m": 80, "wind_speed_10m": 4.0, "wind_direction_10m": 180,
+                "uv_index": 0.0, "is_day": 0
+            },
+            "hourly": {
+                "time": times
+                    .iter()
+                    .map(|time| time.format("%Y-%m-%dT%H:%M").to_string())
+                    .collect::<Vec<_>>(),
+                "temperature_2m": (0..hours).collect::<Vec<_>>(),
+                "weather_code": repeat(3.into()),
+                "precipitation_probability": repeat(10.into()),
+                "apparent_temperature": repeat(48.0.into()),
+                "relative_humidity_2m": repeat(80.into()),
+                "wind_speed_10m": repeat(4.0.into()),
+                "wind_direction_10m": repeat(180.into()),
+                "uv_index": repeat(0.0.into()),
+                "is_day": repeat(1.into())
+            },
+            "daily": {
+                "time": dates,
+                "temperature_2m_max": vec![60.0; dates.len()],
+            }
+        })
+    }
+
+    #[test]
+    fn it_should_match_hours_across_dst_transitions() {
         let config = AppWeatherConfig {
+            hours: vec![1, 2, 3, 12],
+            days: 2,
+            day_mode: WeatherDayMode::NextDays,
+            ..new_config()
+        };
+        let temperatures = |day: &DayWeather| {
+            day.hours
+                .iter()
+                .map(|hour| hour.map(|hour| hour.temperature))
+                .collect::<Vec<_>>()
+        };
+
+        // Clocks go forward at 2am on 2025-03-09, the forecast starts at midnight the day before.
+        let data = new_dst_forecast("2025-03-08T00:00:00-08:00", 72);
+        let result = parse_open_meteo(at("2025-03-08T10:00:00-08:00"), &data, &config).unwrap();
+        assert_eq!(
+            temperatures(&result.days[0]),
+            vec![Some(1.0), Some(2.0), Some(3.0), Some(12.0)]
+        );
+        assert_eq!(
+            temperatures(&result.days[1]),
+            vec![Some(25.0), None, Some(26.0), Some(35.0)]
+        );
+
+        // Clocks go back at 2am on 2025-11-02, 1am happens twice.
+        let data = new_dst_forecast("2025-11-01T00:00:00-07:00", 72);
+        let result = parse_open_meteo(at("2025-11-01T10:00:00-07:00"), &data, &config).unwrap();
+        assert_eq!(
+            temperatures(&result.days[1]),
+            vec![Some(25.0), Some(27.0), Some(28.0), Some(37.0)]
+        );
+        assert_eq!(result.days[1].temperature_max, Some(60.0));
+        assert_eq!(result.days[1].sunrise, None);
+    }
+
+    #[test]
+    fn it_should_leave_missing_slots_empty() {
+        // The forecast starts at 6pm, so earlier hours today and days past its end are missing.
+        let data = new_dst_forecast("2025-05-21T18:00:00-07:00", 30);
+        let result =
+            parse_open_meteo(at("2025-05-21T18:30:00-07:00"), &data, &new_config()).unwrap();
+        assert_eq!(result.days[0].hours[..4], [None, None, None, None]);
+        assert_eq!(result.days[0].hours[4].unwrap().temperature, 0.0);
+        assert_eq!(result.days[1].hours[0].unwrap().temperature, 13.0);
+        assert_eq!(result.days[2].title, "Sat");
+        assert!(result.days[2].hours.iter().all(Option::is_none));
+        assert_eq!(result.days[2].temperature_max, None);
+    }
+
+    pub(super) fn new_config() -> AppWeatherConfig {
+        AppWeatherConfig {
+            provider: WeatherProviderKind::OpenMeteo,
+            user_agent: default_user_agent(),
             latitude: "45.5234".to_string(),
             longitude: "-122.6762".to_string(),
             timezone: "America/Los_Angeles".to_string(),
+            units: WeatherUnitSystem::Imperial,
+            temperature_unit: None,
+            wind_speed_unit: None,
+            precipitation_unit: None,
+            hours: default_hours(),
+            days: default_days(),
+            day_mode: WeatherDayMode::Weekend,
+        }
+    }
+
+    #[test]
+    fn it_should_resolve_units() {
+        assert_eq!(
+            new_config().unit_symbols(),
+            WeatherUnits {
+                temperature: "°F".to_string(),
+                wind_speed: "mph".to_string(),
+                precipitation: "in".to_string(),
+            }
+        );
+        let config: AppWeatherConfig = serde_json::from_value(serde_json::json!({
+            "latitude": "52.52",
+            "longitude": "13.41",
+            "timezone": "Europe/Berlin",
+            "units": "metric",
+            "wind_speed_unit": "ms"
+        }))
+        .unwrap();
+        assert_eq!(config.temperature_unit(), TemperatureUnit::Celsius);
+        assert_eq!(config.precipitation_unit(), PrecipitationUnit::Mm);
+        assert_eq!(config.unit_symbols().wind_speed, "m/s");
+    }
+
+    #[test]
+    fn it_should_use_night_icons_after_dark() {
+        let data = serde_json::from_str::<Value>(include_str!("sample_forecast.json")).unwrap();
+        let config = AppWeatherConfig {
+            hours: vec![2, 12],
+            ..new_config()
         };
-        let result = get_weather(&config).await;
-        assert!(result.is_ok());
-        let result = result.unwrap();
-        println!("{:?}", result);
+        let result = parse_open_meteo(at("2025-05-24T14:30:00-07:00"), &data, &config).unwrap();
+        // The sample forecast is clear at 2am and noon on 2025-05-24, and partly cloudy at 2am
+        // the day after.
+        let night = result.days[0].hours[0].unwrap();
+        let noon = result.days[0].hours[1].unwrap();
+        assert_eq!((night.icon, night.is_day), (ClearNight, false));
+        assert_eq!((noon.icon, noon.is_day), (Sunny, true));
+        assert_eq!(result.days[1].hours[0].unwrap().icon, PartlyCloudyNight);
+        assert_eq!(result.current.icon, Sunny);
     }
 }
@@ -0,0 +1,355 @@
+use crate::context::weather::{
+    AppWeatherConfig, Forecast, HourWeather, WeatherIcon, WeatherProvider, apparent_temperature,
+    daily_from_hours, round,
+};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::warn;
+
+const BASE_URL: &str = "https://api.weather.gov";
+
+/// The hourly forecast of the National Weather Service, whose grid point is looked up first.
+/// Grid points don't move, so their forecast URL is kept for each coordinate.
+pub struct Nws {
+    base_url: String,
+    forecast_urls: Mutex<HashMap<String, String>>,
+}
+
+impl Default for Nws {
+    fn default() -> Nws {
+        Nws {
+            base_url: BASE_URL.to_string(),
+            forecast_urls: Mutex::default(),
+        }
+    }
+}
+
+impl WeatherProvider for Nws {
+    async fn forecast(&self, config: &AppWeatherConfig, tz: Tz) -> anyhow::Result<Forecast> {
+        let client = reqwest::Client::new();
+        let url = self.forecast_url(&client, config).await?;
+        parse_forecast(&get(&client, config, &url).await?, config, tz)
+    }
+}
+
+impl Nws {
+    async fn forecast_url(
+        &self,
+        client: &reqwest::Client,
+        config: &AppWeatherConfig,
+    ) -> anyhow::Result<String> {
+        let (latitude, longitude) = config.coordinates()?;
+        let points_url = format!("{}/points/{:.4},{:.4}", self.base_url, latitude, longitude);
+        if let Some(url) = self
+            .forecast_urls
+            .lock()
+            .map_err(|e| anyhow!("{}", e))?
+            .get(&points_url)
+        {
+            return Ok(url.clone());
+        }
+        let points = get(client, config, &points_url).await?;
+        let url = points["properties"]["forecastHourly"]
+            .as_str()
+            .context("Missing properties.forecastHourly in points response")?
+            .to_string();
+        self.forecast_urls
+            .lock()
+            .map_err(|e| anyhow!("{}", e))?
+            .insert(points_url, url.clone());
+        Ok(url)
+    }
+}
+
+async fn get(
+    client: &reqwest::Client,
+    config: &AppWeatherConfig,
+    url: &str,
+) -> anyhow::Result<Value> {
+    let result = client
+        .get(url)
+        .header("User-Agent", &config.user_agent)
+        .header("Accept", "application/geo+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(result)
+}
+
+/// Periods start at local times, with temperatures in the unit of the `temperatureUnit` field and
+/// wind speeds such as `5 to 10 mph`.
+fn parse_forecast(data: &Value, config: &AppWeatherConfig, tz: Tz) -> anyhow::Result<Forecast> {
+    let periods = data["properties"]["periods"]
+        .as_array()
+        .context("Missing properties.periods in forecast")?;
+    let mut current = None;
+    let mut hours = HashMap::new();
+    for (index, period) in periods.iter().enumerate() {
+        match parse_period(period, config, tz) {
+            Ok((time, hour)) => {
+                current.get_or_insert(hour);
+                hours.entry(time).or_insert(hour);
+            }
+            Err(e) => warn!("Skipping invalid forecast period {}: {:#}", index, e),
+        }
+    }
+    Ok(Forecast {
+        current: current.context("Empty forecast")?,
+        days: daily_from_hours(&hours, config, tz)?,
+        hours,
+    })
+}
+
+fn parse_period(
+    period: &Value,
+    config: &AppWeatherConfig,
+    tz: Tz,
+) -> anyhow::Result<(NaiveDateTime, HourWeather)> {
+    let start = period["startTime"].as_str().context("Missing startTime")?;
+    let time = DateTime::parse_from_rfc3339(start)?
+        .with_timezone(&tz)
+        .naive_local();
+    let temperature = period["temperature"]
+        .as_f64()
+        .context("Missing temperature")?;
+    let celsius = match period["temperatureUnit"].as_str() {
+        Some("F") => (temperature - 32.0) * 5.0 / 9.0,
+        Some("C") => temperature,
+        unit => return Err(anyhow!("Invalid temperature unit {:?}", unit)),
+    };
+    let humidity = period["relativeHumidity"]["value"]
+        .as_f64()
+        .unwrap_or_default();
+    let wind_speed = wind_speed(period["windSpeed"].as_str().unwrap_or_default())?;
+    let is_day = period["isDaytime"].as_bool().unwrap_or(true);
+    let temperature_unit = config.temperature_unit();
+    Ok((
+        time,
+        HourWeather {
+            icon: icon(period["icon"].as_str().unwrap_or_default(), is_day),
+            temperature: round(temperature_unit.convert_celsius(celsius)),
+            hour: time.hour() as usize,
+            precipitation_probability: period["probabilityOfPrecipitation"]["value"]
+                .as_f64()
+                .unwrap_or_default(),
+            apparent_temperature: round(
+                temperature_unit
+                    .convert_celsius(apparent_temperature(celsius, humidity, wind_speed)),
+            ),
+            humidity,
+            wind_speed: round(
+                config
+                    .wind_speed_unit()
+                    .convert_meters_per_second(wind_speed),
+            ),
+            wind_direction: wind_direction(period["windDirection"].as_str().unwrap_or_default()),
+            uv_index: None,
+            is_day,
+        },
+    ))
+}
+
+/// Meters per second from the upper end of a range such as `5 to 10 mph` or `16 km/h`.
+fn wind_speed(value: &str) -> anyhow::Result<f64> {
+    let mut parts = value.split_whitespace().rev();
+    let (unit, speed) = (parts.next(), parts.next());
+    let speed = speed
+        .and_then(|speed| speed.parse::<f64>().ok())
+        .context(format!("Invalid wind speed {}", value))?;
+    match unit {
+        Some("mph") => Ok(speed * 0.44704),
+        Some("km/h") => Ok(speed / 3.6),
+        _ => Err(anyhow!("Invalid wind speed {}", value)),
+    }
+}
+
+/// Degrees for a 16 point compass direction such as `NNW`, calm winds have no direction.
+fn wind_direction(value: &str) -> f64 {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    POINTS
+        .iter()
+        .position(|point| *point == value)
+        .map(|index| index as f64 * 22.5)
+        .unwrap_or_default()
+}
+
+/// Icon URLs look like `https://api.weather.gov/icons/land/night/tsra_hi,40?size=small`, with a
+/// second condition appended when the weather changes within the period.
+fn icon(url: &str, is_day: bool) -> WeatherIcon {
+    let path = url.split('?').next().unwrap_or_default();
+    let condition = path
+        .split('/')
+        .skip_while(|segment| *segment != "day" && *segment != "night")
+        .nth(1)
+        .and_then(|segment| segment.split(',').next())
+        .unwrap_or_default();
+    match condition.trim_start_matches("wind_") {
+        "skc" | "few" | "hot" | "cold" if is_day => WeatherIcon::Sunny,
+        "skc" | "few" | "hot" | "cold" => WeatherIcon::ClearNight,
+        "sct" | "bkn" if is_day => WeatherIcon::PartlyCloudyDay,
+        "sct" | "bkn" => WeatherIcon::PartlyCloudyNight,
+        "ovc" => WeatherIcon::Cloudy,
+        "fog" | "haze" | "smoke" | "dust" => WeatherIcon::Foggy,
+        "rain_showers" | "rain_showers_hi" => WeatherIcon::RainyLight,
+        "rain" => WeatherIcon::Rainy,
+        "rain_snow" | "rain_sleet" | "snow_sleet" | "sleet" => WeatherIcon::AcUnit,
+        "fzra" | "rain_fzra" | "snow_fzra" => WeatherIcon::SevereCold,
+        "snow" => WeatherIcon::WeatherSnowy,
+        "blizzard" => WeatherIcon::SnowingHeavy,
+        "tsra" | "tsra_sct" | "tsra_hi" => WeatherIcon::Thunderstorm,
+        _ => WeatherIcon::Help,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::tests::serve;
+    use crate::context::weather::WeatherIcon::{
+        ClearNight, PartlyCloudyDay, RainyLight, Sunny, Thunderstorm,
+    };
+    use crate::context::weather::tests::{at, new_config};
+    use crate::context::weather::{AppWeatherConfig, WeatherUnitSystem, parse_weather_data};
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use chrono_tz::America::Los_Angeles;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn sample() -> Value {
+        serde_json::from_str(include_str!("sample_nws_forecast.json")).unwrap()
+    }
+
+    #[test]
+    fn it_should_parse_sample_forecast() {
+        let config = AppWeatherConfig {
+            hours: vec![7, 15, 22],
+            ..new_config()
+        };
+        let forecast = parse_forecast(&sample(), &config, Los_Angeles).unwrap();
+        assert_eq!(forecast.hours.len(), 48);
+        assert_eq!(forecast.current.temperature, 72.0);
+        assert_eq!(forecast.current.humidity, 46.0);
+        assert_eq!(forecast.current.wind_speed, 6.0);
+        assert_eq!(forecast.current.wind_direction, 315.0);
+        assert_eq!(forecast.current.uv_index, None);
+        assert_eq!(forecast.current.icon, Sunny);
+
+        // The sample forecast starts at 2pm on Saturday 2025-05-24.
+        let result =
+            parse_weather_data(at("2025-05-24T14:30:00-07:00"), &forecast, &config).unwrap();
+        let today = &result.days[0];
+        assert_eq!(today.hours[0], None);
+        assert_eq!(today.hours[1].unwrap().temperature, 72.0);
+        assert_eq!(today.hours[2].unwrap().icon, ClearNight);
+        assert!(!today.hours[2].unwrap().is_day);
+        assert_eq!(today.temperature_max, Some(72.0));
+        assert_eq!(today.sunrise.as_deref(), Some("05:30"));
+        assert_eq!(today.sunset.as_deref(), Some("20:45"));
+        let tomorrow = &result.days[1];
+        assert_eq!(tomorrow.hours[1].unwrap().icon, PartlyCloudyDay);
+        assert_eq!(tomorrow.hours[2].unwrap().icon, RainyLight);
+        assert_eq!(tomorrow.precipitation_probability_max, Some(40.0));
+        assert_eq!(tomorrow.uv_index_max, None);
+    }
+
+    #[test]
+    fn it_should_convert_to_metric() {
+        let config = AppWeatherConfig {
+            units: WeatherUnitSystem::Metric,
+            ..new_config()
+        };
+        let forecast = parse_forecast(&sample(), &config, Los_Angeles).unwrap();
+        // 72°F with 46% humidity and a 6 mph wind.
+        assert_eq!(forecast.current.temperature, 22.2);
+        assert_eq!(forecast.current.wind_speed, 9.7);
+        assert_eq!(forecast.current.apparent_temperature, 20.4);
+    }
+
+    #[test]
+    fn it_should_skip_invalid_periods() {
+        let mut data = sample();
+        data["properties"]["periods"][0]["windSpeed"] = Value::from("");
+        data["properties"]["periods"][5]["temperature"] = Value::Null;
+        let forecast = parse_forecast(&data, &new_config(), Los_Angeles).unwrap();
+        assert_eq!(forecast.hours.len(), 46);
+        // The current weather comes from the first valid period.
+        let second = parse_period(
+            &sample()["properties"]["periods"][1],
+            &new_config(),
+            Los_Angeles,
+        );
+        assert_eq!(forecast.current, second.unwrap().1);
+    }
+
+    #[tokio::test]
+    async fn it_should_look_up_grid_point_once() {
+        let points = Arc::new(AtomicUsize::new(0));
+        let counter = points.clone();
+        let url = serve(
+            Router::new()
+                .route(
+                    "/points/{point}",
+                    get(move |headers: HeaderMap| {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        let host = headers["host"].to_str().unwrap().to_string();
+                        async move {
+                            Json(json!({
+                                "properties": {
+                                    "forecastHourly": format!("http://{}/gridpoints/PQR/112,103/forecast/hourly", host)
+                                }
+                            }))
+                        }
+                    }),
+                )
+                .route(
+                    "/gridpoints/PQR/112,103/forecast/hourly",
+                    get(|| async { include_str!("sample_nws_forecast.json") }),
+                ),
+        )
+        .await;
+        let provider = Nws {
+            base_url: url,
+            ..Nws::default()
+        };
+        for _ in 0..2 {
+            let forecast = provider.forecast(&new_config(), Los_Angeles).await.unwrap();
+            assert_eq!(forecast.hours.len(), 48);
+        }
+        assert_eq!(points.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_should_parse_periods() {
+        assert_eq!(wind_speed("5 to 10 mph").unwrap(), 10.0 * 0.44704);
+        assert_eq!(wind_speed("18 km/h").unwrap(), 5.0);
+        assert!(wind_speed("calm").is_err());
+        assert_eq!(wind_direction("NNW"), 337.5);
+        assert_eq!(wind_direction(""), 0.0);
+        assert_eq!(
+            icon(
+                "https://api.weather.gov/icons/land/night/tsra_hi,30/rain,60?size=small",
+                false
+            ),
+            Thunderstorm
+        );
+        assert_eq!(
+            icon(
+                "https://api.weather.gov/icons/land/day/wind_few?size=small",
+                true
+            ),
+            Sunny
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use crate::context::weather::{
+    AppWeatherConfig, DailyWeather, Forecast, HourWeather, PrecipitationUnit, TemperatureUnit,
+    WeatherIcon, WeatherProvider, WindSpeedUnit,
+};
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use url::Url;
+
+/// Open-Meteo converts units and timezones itself, so responses are used as they are.
+pub struct OpenMeteo;
+
+impl WeatherProvider for OpenMeteo {
+    async fn forecast(&self, config: &AppWeatherConfig, _tz: Tz) -> anyhow::Result<Forecast> {
+        parse_forecast(&get_weather(config).await?)
+    }
+}
+
+type HourIndex = usize;
+
+impl HourWeather {
+    fn extract<T>(
+        data: &Value,
+        hour_index: HourIndex,
+        field: &'static str,
+        mapper: impl Fn(&Value) -> Option<T>,
+    ) -> anyhow::Result<T> {
+        let value = &data["hourly"][field][hour_index];
+        Ok(mapper(value).context(format!(
+            "field error hourly.{}[{}]: {:?}",
+            field, hour_index, value
+        ))?)
+    }
+
+    fn get<T>(
+        data: &Value,
+        field: &'static str,
+        mapper: impl Fn(&Value) -> Option<T>,
+    ) -> anyhow::Result<T> {
+        let value = &data["current"][field];
+        Ok(mapper(value).context(format!("field error current.[{}]: {:?}", field, value))?)
+    }
+
+    fn current(data: &Value) -> anyhow::Result<HourWeather> {
+        let is_day = Self::get(data, "is_day", |v| v.as_u64().map(|v| v != 0))?;
+        Ok(HourWeather {
+            icon: {
+                let weather_code = Self::get(data, "weather_code", |v| v.as_u64())?;
+                weather_code_to_icon(weather_code, is_day)
+            },
+            temperature: Self::get(data, "temperature_2m", |v| v.as_f64())?,
+            hour: 0,
+            precipitation_probability: 0.0,
+            apparent_temperature: Self::get(data, "apparent_temperature", |v| v.as_f64())?,
+            humidity: Self::get(data, "relative_humidity_2m", |v| v.as_f64())?,
+            wind_speed: Self::get(data, "wind_speed_10m", |v| v.as_f64())?,
+            wind_direction: Self::get(data, "wind_direction_10m", |v| v.as_f64())?,
+            uv_index: Some(Self::get(data, "uv_index", |v| v.as_f64())?),
+            is_day,
+        })
+    }
+
+    fn from(data: &Value, hour_index: HourIndex, hour: usize) -> anyhow::Result<HourWeather> {
+        let is_day = Self::extract(data, hour_index, "is_day", |v| v.as_u64().map(|v| v != 0))?;
+        Ok(HourWeather {
+            icon: {
+                let weather_code = Self::extract(data, hour_index, "weather_code", |v| v.as_u64())?;
+                weather_code_to_icon(weather_code, is_day)
+            },
+            temperature: Self::extract(data, hour_index, "temperature_2m", |v| v.as_f64())?,
+            hour,
+            precipitation_probability: Self::extract(
+                data,
+                hour_index,
+                "precipitation_probability",
+                |v| v.as_f64(),
+            )?,
+            apparent_temperature: Self::extract(data, hour_index, "apparent_temperature", |v| {
+                v.as_f64()
+            })?,
+            humidity: Self::extract(data, hour_index, "relative_humidity_2m", |v| v.as_f64())?,
+            wind_speed: Self::extract(data, hour_index, "wind_speed_10m", |v| v.as_f64())?,
+            wind_direction: Self::extract(data, hour_index, "wind_direction_10m", |v| v.as_f64())?,
+            uv_index: Some(Self::extract(data, hour_index, "uv_index", |v| v.as_f64())?),
+            is_day,
+        })
+    }
+}
+
+impl DailyWeather {
+    fn extract<T>(
+        data: &Value,
+        day_index: usize,
+        field: &'static str,
+        mapper: impl Fn(&Value) -> Option<T>,
+    ) -> Option<T> {
+        mapper(&data["daily"][field][day_index])
+    }
+
+    /// Open-Meteo returns local times such as `2025-05-24T05:33`, only the time is kept.
+    fn time_of_day(value: &Value) -> Option<String> {
+        let (_, time) = value.as_str()?.split_once('T')?;
+        Some(time.to_string())
+    }
+
+    fn from(data: &Value, day_index: usize) -> DailyWeather {
+        DailyWeather {
+            temperature_max: Self::extract(data, day_index, "temperature_2m_max", |v| v.as_f64()),
+            temperature_min: Self::extract(data, day_index, "temperature_2m_min", |v| v.as_f64()),
+            precipitation_probability_max: Self::extract(
+                data,
+                day_index,
+                "precipitation_probability_max",
+                |v| v.as_f64(),
+            ),
+            uv_index_max: Self::extract(data, day_index, "uv_index_max", |v| v.as_f64()),
+            sunrise: Self::extract(data, day_index, "sunrise", Self::time_of_day),
+            sunset: Self::extract(data, day_index, "sunset", Self::time_of_day),
+        }
+    }
+}
+
+fn times(data: &Value, section: &str) -> Vec<String> {
+    data[section]["time"]
+        .as_array()
+        .map(|times| {
+            times
+                .iter()
+                .filter_map(|time| time.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Hourly and daily entries are keyed by their local `time`. Days around DST transitions have
+/// 23 or 25 hours, so array positions can't be computed from the date and hour.
+pub fn parse_forecast(data: &Value) -> anyhow::Result<Forecast> {
+    let mut hours = HashMap::new();
+    for (index, time) in times(data, "hourly").iter().enumerate() {
+        // The repeated hour when clocks go back keeps its first occurrence. Hours with missing
+        // values, which Open-Meteo returns as null, are left out like unforecast hours.
+        if let Ok(time) = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")
+            && !hours.contains_key(&time)
+            && let Ok(hour) = HourWeather::from(data, index, time.hour() as usize)
+        {
+            hours.insert(time, hour);
+        }
+    }
+    let days = times(data, "daily")
+        .iter()
+        .enumerate()
+        .filter_map(|(index, date)| {
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+            Some((date, DailyWeather::from(data, index)))
+        })
+        .collect();
+    Ok(Forecast {
+        current: HourWeather::current(data)?,
+        hours,
+        days,
+    })
+}
+
+fn weather_code_to_icon(code: u64, is_day: bool) -> WeatherIcon {
+    match code {
+        0 if is_day => WeatherIcon::Sunny,
+        0 => WeatherIcon::ClearNight,
+        1 | 2 if is_day => WeatherIcon::PartlyCloudyDay,
+        1 | 2 => WeatherIcon::PartlyCloudyNight,
+        3 => WeatherIcon::Cloudy,
+        45 | 48 => WeatherIcon::Foggy,
+        51 | 61 | 80 => WeatherIcon::RainyLight,
+        53 | 63 | 81 => WeatherIcon::Rainy,
+        55 | 65 | 82 => WeatherIcon::RainyHeavy,
+        56 | 66 => WeatherIcon::AcUnit,
+        57 | 67 => WeatherIcon::SevereCold,
+        71 | 85 => WeatherIcon::WeatherSnowy,
+        73 => WeatherIcon::Snowing,
+        75 | 86 => WeatherIcon::SnowingHeavy,
+        77 => WeatherIcon::Grain,
+        95 | 96 | 99 => WeatherIcon::Thunderstorm,
+        _ => WeatherIcon::Help,
+    }
+}
+
+async fn get_weather(config: &AppWeatherConfig) -> anyhow::Result<Value> {
+    let mut url = Url::from_str("https://api.open-meteo.com")?;
+    url.set_path("/v1/forecast");
+    url.query_pairs_mut()
+        .append_pair("latitude", &config.latitude)
+        .append_pair("longitude", &config.longitude)
+        .append_pair(
+            "hourly",
+            "temperature_2m,rain,precipitation_probability,weather_code,apparent_temperature,\
+             relative_humidity_2m,wind_speed_10m,wind_direction_10m,uv_index,is_day",
+        )
+        .append_pair(
+            "current",
+            "temperature_2m,rain,precipitation_probability,weather_code,apparent_temperature,\
+             relative_humidity_2m,wind_speed_10m,wind_direction_10m,uv_index,is_day",
+        )
+        .append_pair(
+            "daily",
+            "weather_code,temperature_2m_max,temperature_2m_min,sunrise,sunset,uv_index_max,\
+             precipitation_probability_max",
+        )
+        .append_pair("timezone", &config.timezone)
+        .append_pair(
+            "wind_speed_unit",
+            match config.wind_speed_unit() {
+                WindSpeedUnit::Kmh => "kmh",
+                WindSpeedUnit::Ms => "ms",
+                WindSpeedUnit::Mph => "mph",
+                WindSpeedUnit::Kn => "kn",
+            },
+        )
+        .append_pair(
+            "temperature_unit",
+            match config.temperature_unit() {
+                TemperatureUnit::Celsius => "celsius",
+                TemperatureUnit::Fahrenheit => "fahrenheit",
+            },
+        )
+        .append_pair("forecast_days", "14")
+        .append_pair(
+            "precipitation_unit",
+            match config.precipitation_unit() {
+                PrecipitationUnit::Mm => "mm",
+                PrecipitationUnit::Inch => "inch",
+            },
+        );
+
+    let result = reqwest::get(url).await?.json().await?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::weather::WeatherIcon::{
+        ClearNight, Cloudy, PartlyCloudyDay, PartlyCloudyNight, Sunny,
+    };
+    use crate::context::weather::tests::new_config;
+
+    #[test]
+    fn it_should_convert_weather_code_to_icon() {
+        assert_eq!(weather_code_to_icon(0, true), Sunny);
+        assert_eq!(weather_code_to_icon(0, false), ClearNight);
+        assert_eq!(weather_code_to_icon(2, true), PartlyCloudyDay);
+        assert_eq!(weather_code_to_icon(1, false), PartlyCloudyNight);
+        assert_eq!(weather_code_to_icon(3, false), Cloudy);
+    }
+
+    #[test]
+    fn it_should_parse_sample_forecast() {
+        let data = serde_json::from_str::<Value>(include_str!("sample_forecast.json")).unwrap();
+        let forecast = parse_forecast(&data).unwrap();
+        assert_eq!(forecast.hours.len(), 336);
+        assert_eq!(forecast.days.len(), 14);
+        let time = NaiveDateTime::parse_from_str("2025-05-24T15:00", "%Y-%m-%dT%H:%M").unwrap();
+        assert_eq!(forecast.hours[&time].hour, 15);
+    }
+
+    #[test]
+    fn it_should_skip_hours_with_missing_values() {
+        let mut data = serde_json::from_str::<Value>(include_str!("sample_forecast.json")).unwrap();
+        data["hourly"]["temperature_2m"][300] = Value::Null;
+        let forecast = parse_forecast(&data).unwrap();
+        assert_eq!(forecast.hours.len(), 335);
+        let time = |time| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").unwrap();
+        let skipped = data["hourly"]["time"][300].as_str().unwrap();
+        assert!(!forecast.hours.contains_key(&time(skipped)));
+        assert_eq!(forecast.hours[&time("2025-05-24T15:00")].hour, 15);
+    }
+
+    #[ignore = "requires internet connection"]
+    #[tokio::test]
+    async fn it_should_get_weather() {
+        let result = get_weather(&new_config()).await;
+        assert!(result.is_ok());
+        let result = result.unwrap();
+        println!("{:?}", result);
+    }
+}
@@ -0,0 +1,369 @@
+use minijinja::value::Kwargs;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Numbers the fill patterns of area charts so several charts on one page don't share an id.
+static DITHER_PATTERN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+pub type Chart = fn(&[Option<f64>], &ChartOptions) -> String;
+
+pub struct ChartOptions {
+    pub width: f64,
+    pub height: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub stroke_width: f64,
+    pub ticks: usize,
+    pub font_size: f64,
+    pub gap: f64,
+}
+
+impl ChartOptions {
+    pub fn from_kwargs(
+        width: f64,
+        height: f64,
+        kwargs: &Kwargs,
+    ) -> Result<ChartOptions, minijinja::Error> {
+        Ok(ChartOptions {
+            width,
+            height,
+            min: kwargs.get("min")?,
+            max: kwargs.get("max")?,
+            stroke_width: kwargs.get::<Option<f64>>("stroke_width")?.unwrap_or(2.0),
+            ticks: kwargs.get::<Option<usize>>("ticks")?.unwrap_or(0),
+            font_size: kwargs.get::<Option<f64>>("font_size")?.unwrap_or(14.0),
+            gap: kwargs.get::<Option<f64>>("gap")?.unwrap_or(2.0),
+        })
+    }
+}
+
+struct Scale {
+    min: f64,
+    max: f64,
+    left: f64,
+    width: f64,
+    height: f64,
+    count: usize,
+}
+
+impl Scale {
+    fn new(values: &[Option<f64>], options: &ChartOptions) -> Scale {
+        let present = values.iter().flatten();
+        let data_min = present.clone().cloned().fold(f64::INFINITY, f64::min);
+        let data_max = present.cloned().fold(f64::NEG_INFINITY, f64::max);
+        let min = options.min.unwrap_or(data_min);
+        let max = options.max.unwrap_or(data_max);
+        let (min, max) = match (min.is_finite(), max.is_finite()) {
+            (true, true) if min < max => (min, max),
+            // An inverted range, or an explicit bound on the wrong side of all the data, covers
+            // both bounds and the data instead
+            (true, true) if min > max => (min.min(max).min(data_min), min.max(max).max(data_max)),
+            (true, true) => (min - 1.0, max + 1.0),
+            (true, false) => (min, min + 1.0),
+            (false, true) => (max - 1.0, max),
+            (false, false) => (0.0, 1.0),
+        };
+        let left = if options.ticks > 0 {
+            options.font_size * 3.0
+        } else {
+            0.0
+        };
+        Scale {
+            min,
+            max,
+            left,
+            width: options.width - left,
+            height: options.height,
+            count: values.len(),
+        }
+    }
+
+    fn x(&self, index: usize) -> f64 {
+        if self.count < 2 {
+            return self.left + self.width / 2.0;
+        }
+        self.left + self.width * index as f64 / (self.count - 1) as f64
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        let value = value.clamp(self.min, self.max);
+        self.height - self.height * (value - self.min) / (self.max - self.min)
+    }
+
+    fn slot_width(&self) -> f64 {
+        self.width / self.count.max(1) as f64
+    }
+}
+
+/// Splits the series into runs of consecutive present values so missing values leave gaps.
+fn segments(values: &[Option<f64>]) -> Vec<Vec<(usize, f64)>> {
+    let mut segments = vec![];
+    let mut segment = vec![];
+    for (index, value) in values.iter().enumerate() {
+        match value {
+            Some(value) => segment.push((index, *value)),
+            None if !segment.is_empty() => segments.push(std::mem::take(&mut segment)),
+            None => {}
+        }
+    }
+    if !segment.is_empty() {
+        segments.push(segment);
+    }
+    segments
+}
+
+fn line_path(scale: &Scale, values: &[Option<f64>]) -> String {
+    let mut d = String::new();
+    for segment in segments(values) {
+        for (i, (index, value)) in segment.iter().enumerate() {
+            let command = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(
+                d,
+                "{}{:.1} {:.1} ",
+                command,
+                scale.x(*index),
+                scale.y(*value)
+            );
+        }
+    }
+    d.trim_end().to_string()
+}
+
+fn area_path(scale: &Scale, values: &[Option<f64>]) -> String {
+    let mut d = String::new();
+    let baseline = scale.height;
+    for segment in segments(values) {
+        let (first, _) = segment[0];
+        let (last, _) = segment[segment.len() - 1];
+        let _ = write!(d, "M{:.1} {:.1} ", scale.x(first), baseline);
+        for (index, value) in &segment {
+            let _ = write!(d, "L{:.1} {:.1} ", scale.x(*index), scale.y(*value));
+        }
+        let _ = write!(d, "L{:.1} {:.1} Z ", scale.x(last), baseline);
+    }
+    d.trim_end().to_string()
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract().abs() < f64::EPSILON {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+fn axis(scale: &Scale, options: &ChartOptions) -> String {
+    if options.ticks == 0 {
+        return String::new();
+    }
+    let mut markup = format!(
+        r#"<path d="M{:.1} 0 V{:.1} H{:.1}" fill="none" stroke="black" stroke-width="1"/>"#,
+        scale.left,
+        scale.height,
+        scale.left + scale.width
+    );
+    for i in 0..options.ticks {
+        let value = if options.ticks == 1 {
+            scale.min
+        } else {
+            scale.min + (scale.max - scale.min) * i as f64 / (options.ticks - 1) as f64
+        };
+        let y = scale.y(value);
+        let _ = write!(
+            markup,
+            r#"<path d="M{:.1} {:.1} h-4" stroke="black" stroke-width="1"/><text x="{:.1}" y="{:.1}" font-size="{}" text-anchor="end">{}</text>"#,
+            scale.left,
+            y,
+            scale.left - 6.0,
+            y + options.font_size / 3.0,
+            options.font_size,
+            format_number(value)
+        );
+    }
+    markup
+}
+
+pub fn sparkline(values: &[Option<f64>], options: &ChartOptions) -> String {
+    let scale = Scale::new(values, options);
+    format!(
+        r#"<g class="chart sparkline">{}<path d="{}" fill="none" stroke="black" stroke-width="{}" stroke-linejoin="round" stroke-linecap="round"/></g>"#,
+        axis(&scale, options),
+        line_path(&scale, values),
+        options.stroke_width
+    )
+}
+
+pub fn area_chart(values: &[Option<f64>], options: &ChartOptions) -> String {
+    let scale = Scale::new(values, options);
+    let id = format!(
+        "chart-dither-{}",
+        DITHER_PATTERN_COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    format!(
+        r#"<g class="chart area-chart"><defs><pattern id="{id}" patternUnits="userSpaceOnUse" width="4" height="4"><rect width="4" height="4" fill="white"/><rect width="2" height="2" fill="black"/><rect x="2" y="2" width="2" height="2" fill="black"/></pattern></defs>{}<path d="{}" fill="url(#{id})" stroke="none"/><path d="{}" fill="none" stroke="black" stroke-width="{}" stroke-linejoin="round"/></g>"#,
+        axis(&scale, options),
+        area_path(&scale, values),
+        line_path(&scale, values),
+        options.stroke_width,
+    )
+}
+
+pub fn bar_chart(values: &[Option<f64>], options: &ChartOptions) -> String {
+    let scale = Scale::new(values, options);
+    let baseline = scale.y(scale.min.max(0.0).min(scale.max));
+    let slot_width = scale.slot_width();
+    let bar_width = (slot_width - options.gap).max(1.0);
+    let mut bars = String::new();
+    for (index, value) in values.iter().enumerate() {
+        let Some(value) = value else { continue };
+        let x = scale.left + slot_width * index as f64 + (slot_width - bar_width) / 2.0;
+        let y = scale.y(*value);
+        let _ = write!(
+            bars,
+            r#"<rect x="{:.0}" y="{:.0}" width="{:.0}" height="{:.0}" fill="black"/>"#,
+            x,
+            y.min(baseline),
+            bar_width,
+            (baseline - y).abs()
+        );
+    }
+    format!(
+        r#"<g class="chart bar-chart" shape-rendering="crispEdges">{}{}</g>"#,
+        axis(&scale, options),
+        bars
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(ticks: usize) -> ChartOptions {
+        ChartOptions {
+            width: 100.0,
+            height: 50.0,
+            min: None,
+            max: None,
+            stroke_width: 2.0,
+            ticks,
+            font_size: 10.0,
+            gap: 2.0,
+        }
+    }
+
+    #[test]
+    fn it_should_scale_line_to_box() {
+        let values = [Some(0.0), Some(5.0), Some(10.0)];
+        let scale = Scale::new(&values, &options(0));
+        assert_eq!(
+            line_path(&scale, &values),
+            "M0.0 50.0 L50.0 25.0 L100.0 0.0"
+        );
+    }
+
+    #[test]
+    fn it_should_leave_gaps_for_missing_values() {
+        let values = [Some(1.0), None, Some(2.0), Some(3.0)];
+        let scale = Scale::new(&values, &options(0));
+        let d = line_path(&scale, &values);
+        assert_eq!(d.matches('M').count(), 2);
+        let d = area_path(&scale, &values);
+        assert_eq!(d.matches('Z').count(), 2);
+    }
+
+    #[test]
+    fn it_should_reserve_space_for_axis_ticks() {
+        let values = [Some(0.0), Some(10.0)];
+        let markup = sparkline(&values, &options(3));
+        assert_eq!(markup.matches("<text").count(), 3);
+        assert!(markup.contains(">5</text>"));
+        assert!(markup.contains("M30.0 50.0 L100.0 0.0"));
+    }
+
+    #[test]
+    fn it_should_draw_one_bar_per_value() {
+        let values = [Some(20.0), None, Some(80.0), Some(0.0)];
+        let markup = bar_chart(
+            &values,
+            &ChartOptions {
+                min: Some(0.0),
+                max: Some(100.0),
+                ..options(0)
+            },
+        );
+        assert_eq!(markup.matches("<rect").count(), 3);
+        assert!(markup.contains(r#"<rect x="51" y="10" width="23" height="40" fill="black"/>"#));
+    }
+
+    #[test]
+    fn it_should_handle_flat_and_empty_series() {
+        let flat = [Some(3.0), Some(3.0)];
+        let scale = Scale::new(&flat, &options(0));
+        assert_eq!(scale.y(3.0), 25.0);
+        assert_eq!(line_path(&Scale::new(&[], &options(0)), &[]), "");
+        let scale = Scale::new(
+            &[],
+            &ChartOptions {
+                min: Some(5.0),
+                max: Some(5.0),
+                ..options(0)
+            },
+        );
+        assert_eq!(scale.y(5.0), 25.0);
+    }
+
+    #[test]
+    fn it_should_widen_inverted_ranges() {
+        let values = [Some(-10.0), Some(-2.0)];
+        let scale = Scale::new(
+            &values,
+            &ChartOptions {
+                min: Some(0.0),
+                ..options(0)
+            },
+        );
+        assert_eq!((scale.min, scale.max), (-10.0, 0.0));
+        assert_eq!(line_path(&scale, &values), "M0.0 50.0 L100.0 10.0");
+
+        let scale = Scale::new(
+            &values,
+            &ChartOptions {
+                min: Some(10.0),
+                max: Some(0.0),
+                ..options(0)
+            },
+        );
+        assert_eq!((scale.min, scale.max), (-10.0, 10.0));
+        assert!(sparkline(&values, &options(3)).contains("M30.0 50.0"));
+    }
+
+    #[test]
+    fn it_should_clamp_values_outside_explicit_bounds() {
+        let values = [Some(-5.0), Some(50.0), Some(150.0)];
+        let scale = Scale::new(
+            &values,
+            &ChartOptions {
+                min: Some(0.0),
+                max: Some(100.0),
+                ..options(0)
+            },
+        );
+        assert_eq!(
+            line_path(&scale, &values),
+            "M0.0 50.0 L50.0 25.0 L100.0 0.0"
+        );
+    }
+
+    #[test]
+    fn it_should_give_each_area_chart_its_own_pattern() {
+        let values = [Some(1.0), Some(2.0)];
+        let first = area_chart(&values, &options(0));
+        let second = area_chart(&values, &options(0));
+        let id = |markup: &str| {
+            let start = markup.find(r#"<pattern id=""#).unwrap() + 13;
+            markup[start..start + markup[start..].find('"').unwrap()].to_string()
+        };
+        assert_ne!(id(&first), id(&second));
+        assert!(second.contains(&format!("url(#{})", id(&second))));
+    }
+}
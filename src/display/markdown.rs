@@ -0,0 +1,571 @@
+use crate::display::text::{Font, TextMeasure};
+use anyhow::Result;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::fmt::Write;
+
+const BOLD_WEIGHT: u16 = 700;
+const BULLET: &str = "•";
+
+pub struct MarkdownOptions {
+    pub width: f32,
+    pub height: f32,
+    pub font: Font,
+    pub line_height: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word {
+        text: String,
+        bold: bool,
+        italic: bool,
+        space_before: bool,
+    },
+    Break,
+}
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    Heading(HeadingLevel, Vec<Token>),
+    Paragraph(Vec<Token>),
+    ListItem {
+        marker: String,
+        depth: usize,
+        tokens: Vec<Token>,
+    },
+    Table(Vec<Vec<String>>),
+    Rule,
+}
+
+#[derive(Default)]
+struct BlockParser {
+    blocks: Vec<Block>,
+    tokens: Vec<Token>,
+    bold: usize,
+    italic: usize,
+    pending_space: bool,
+    lists: Vec<Option<u64>>,
+    marker: Option<String>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: Option<String>,
+}
+
+impl BlockParser {
+    fn text(&mut self, text: &str) {
+        if let Some(cell) = &mut self.cell {
+            cell.push_str(text);
+            return;
+        }
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.tokens.push(Token::Break);
+                self.pending_space = false;
+            }
+            let mut space_before = self.pending_space || line.starts_with(char::is_whitespace);
+            for word in line.split_whitespace() {
+                self.tokens.push(Token::Word {
+                    text: word.to_string(),
+                    bold: self.bold > 0,
+                    italic: self.italic > 0,
+                    space_before,
+                });
+                space_before = true;
+            }
+            self.pending_space = line.ends_with(char::is_whitespace);
+        }
+    }
+
+    fn flush(&mut self, block: impl FnOnce(Vec<Token>) -> Block) {
+        let mut tokens = std::mem::take(&mut self.tokens);
+        while tokens.last() == Some(&Token::Break) {
+            tokens.pop();
+        }
+        self.pending_space = false;
+        if !tokens.is_empty() {
+            self.blocks.push(block(tokens));
+        }
+    }
+
+    fn flush_list_item(&mut self) {
+        let marker = self.marker.take().unwrap_or_default();
+        let depth = self.lists.len().saturating_sub(1);
+        self.flush(|tokens| Block::ListItem {
+            marker,
+            depth,
+            tokens,
+        });
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Strong) => self.bold += 1,
+            Event::End(TagEnd::Strong) => self.bold = self.bold.saturating_sub(1),
+            Event::Start(Tag::Emphasis) => self.italic += 1,
+            Event::End(TagEnd::Emphasis) => self.italic = self.italic.saturating_sub(1),
+            Event::Start(Tag::List(start)) => {
+                if !self.lists.is_empty() {
+                    self.flush_list_item();
+                }
+                self.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let marker = format!("{}.", number);
+                        *number += 1;
+                        marker
+                    }
+                    _ => BULLET.to_string(),
+                };
+                self.marker = Some(marker);
+            }
+            Event::End(TagEnd::Item) => self.flush_list_item(),
+            Event::End(TagEnd::Paragraph) | Event::End(TagEnd::CodeBlock) => {
+                if self.lists.is_empty() {
+                    self.flush(Block::Paragraph);
+                } else {
+                    self.flush_list_item();
+                }
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                self.flush(|tokens| Block::Heading(level, tokens))
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => self.row = vec![],
+            Event::End(TagEnd::TableHead) | Event::End(TagEnd::TableRow) => {
+                self.rows.push(std::mem::take(&mut self.row))
+            }
+            Event::Start(Tag::TableCell) => self.cell = Some(String::new()),
+            Event::End(TagEnd::TableCell) => {
+                let cell = self.cell.take().unwrap_or_default();
+                self.row.push(cell.trim().to_string());
+            }
+            Event::End(TagEnd::Table) => {
+                let rows = std::mem::take(&mut self.rows);
+                self.blocks.push(Block::Table(rows));
+            }
+            Event::Text(text) | Event::Code(text) => self.text(&text),
+            Event::SoftBreak => self.pending_space = true,
+            Event::HardBreak => {
+                self.tokens.push(Token::Break);
+                self.pending_space = false;
+            }
+            Event::Rule => self.blocks.push(Block::Rule),
+            _ => {}
+        }
+    }
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut parser = BlockParser::default();
+    for event in Parser::new_ext(markdown, Options::ENABLE_TABLES) {
+        parser.event(event);
+    }
+    parser.blocks
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+struct Layout<'a> {
+    measure: &'a TextMeasure,
+    options: &'a MarkdownOptions,
+    pages: Vec<String>,
+    page: String,
+    y: f32,
+}
+
+impl Layout<'_> {
+    fn font(&self, size: f32, bold: bool) -> Font {
+        Font {
+            family: self.options.font.family.clone(),
+            weight: if bold {
+                BOLD_WEIGHT
+            } else {
+                self.options.font.weight
+            },
+            size,
+        }
+    }
+
+    /// Reserves a line of `size` and returns its baseline, starting a new page when the line
+    /// would overflow the current one.
+    fn line(&mut self, size: f32) -> f32 {
+        let height = size * self.options.line_height;
+        if self.y + height > self.options.height && !self.page.is_empty() {
+            self.pages.push(std::mem::take(&mut self.page));
+            self.y = 0.0;
+        }
+        let baseline = self.y + size + (height - size) / 2.0;
+        self.y += height;
+        baseline
+    }
+
+    fn gap(&mut self, size: f32) {
+        if !self.page.is_empty() {
+            self.y += size * (self.options.line_height - 1.0).max(0.25);
+        }
+    }
+
+    fn emit_line(&mut self, x: f32, size: f32, words: &[Token], marker: Option<(f32, &str)>) {
+        let baseline = self.line(size);
+        if let Some((marker_x, marker)) = marker {
+            let _ = write!(
+                self.page,
+                r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{:.1}" font-weight="{}">{}</text>"#,
+                marker_x,
+                baseline,
+                escape_xml(&self.options.font.family),
+                size,
+                self.options.font.weight,
+                escape_xml(marker)
+            );
+        }
+        let mut tspans = String::new();
+        let mut style = None;
+        for (i, word) in words.iter().enumerate() {
+            let Token::Word {
+                text,
+                bold,
+                italic,
+                space_before,
+            } = word
+            else {
+                continue;
+            };
+            if style != Some((*bold, *italic)) {
+                if style.is_some() {
+                    tspans.push_str("</tspan>");
+                }
+                let _ = write!(
+                    tspans,
+                    r#"<tspan font-weight="{}" font-style="{}">"#,
+                    if *bold { "bold" } else { "normal" },
+                    if *italic { "italic" } else { "normal" }
+                );
+                style = Some((*bold, *italic));
+            }
+            if i > 0 && *space_before {
+                tspans.push(' ');
+            }
+            tspans.push_str(&escape_xml(text));
+        }
+        if style.is_some() {
+            tspans.push_str("</tspan>");
+        }
+        let _ = write!(
+            self.page,
+            r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{:.1}" font-weight="{}">{}</text>"#,
+            x,
+            baseline,
+            escape_xml(&self.options.font.family),
+            size,
+            self.options.font.weight,
+            tspans
+        );
+    }
+
+    fn word_width(&self, word: &Token, size: f32, first: bool) -> Result<f32> {
+        let Token::Word {
+            text,
+            bold,
+            space_before,
+            ..
+        } = word
+        else {
+            return Ok(0.0);
+        };
+        let font = self.font(size, *bold);
+        let text = if *space_before && !first {
+            format!(" {}", text)
+        } else {
+            text.clone()
+        };
+        self.measure.width(&text, &font)
+    }
+
+    /// Wraps the tokens into lines starting at `x`, `marker` is drawn next to the first line.
+    fn paragraph(
+        &mut self,
+        x: f32,
+        size: f32,
+        tokens: &[Token],
+        mut marker: Option<(f32, &str)>,
+    ) -> Result<()> {
+        let width = self.options.width - x;
+        let mut line: Vec<Token> = vec![];
+        let mut line_width = 0.0;
+        for token in tokens {
+            if *token == Token::Break {
+                self.emit_line(x, size, &line, marker.take());
+                line.clear();
+                line_width = 0.0;
+                continue;
+            }
+            let word_width = self.word_width(token, size, line.is_empty())?;
+            if line_width + word_width <= width || line.is_empty() {
+                line_width += word_width;
+                line.push(token.clone());
+                continue;
+            }
+            self.emit_line(x, size, &line, marker.take());
+            line_width = self.word_width(token, size, true)?;
+            line = vec![token.clone()];
+        }
+        if !line.is_empty() || marker.is_some() {
+            self.emit_line(x, size, &line, marker);
+        }
+        Ok(())
+    }
+
+    fn table(&mut self, rows: &[Vec<String>]) -> Result<()> {
+        let size = self.options.font.size;
+        let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0).max(1);
+        let column_width = self.options.width / columns as f32;
+        for (index, row) in rows.iter().enumerate() {
+            let header = index == 0;
+            let font = self.font(size, header);
+            let baseline = self.line(size);
+            for (column, cell) in row.iter().enumerate() {
+                let cell = self
+                    .measure
+                    .truncate(cell, column_width - size / 2.0, &font)?;
+                let _ = write!(
+                    self.page,
+                    r#"<text x="{:.1}" y="{:.1}" font-family="{}" font-size="{:.1}" font-weight="{}">{}</text>"#,
+                    column as f32 * column_width,
+                    baseline,
+                    escape_xml(&font.family),
+                    size,
+                    font.weight,
+                    escape_xml(&cell)
+                );
+            }
+            if header {
+                self.rule();
+            }
+        }
+        Ok(())
+    }
+
+    fn rule(&mut self) {
+        let y = self.y.round() + 0.5;
+        let _ = write!(
+            self.page,
+            r#"<path d="M0 {:.1} H{:.1}" stroke="black" stroke-width="1"/>"#,
+            y, self.options.width
+        );
+    }
+
+    fn block(&mut self, block: &Block) -> Result<()> {
+        let size = self.options.font.size;
+        match block {
+            Block::Heading(level, tokens) => {
+                let scale = match level {
+                    HeadingLevel::H1 => 2.0,
+                    HeadingLevel::H2 => 1.5,
+                    HeadingLevel::H3 => 1.25,
+                    _ => 1.1,
+                };
+                let tokens: Vec<Token> = tokens
+                    .iter()
+                    .map(|token| match token {
+                        Token::Word {
+                            text,
+                            italic,
+                            space_before,
+                            ..
+                        } => Token::Word {
+                            text: text.clone(),
+                            bold: true,
+                            italic: *italic,
+                            space_before: *space_before,
+                        },
+                        Token::Break => Token::Break,
+                    })
+                    .collect();
+                self.gap(size);
+                self.paragraph(0.0, size * scale, &tokens, None)
+            }
+            Block::Paragraph(tokens) => {
+                self.gap(size);
+                self.paragraph(0.0, size, tokens, None)
+            }
+            Block::ListItem {
+                marker,
+                depth,
+                tokens,
+            } => {
+                let x = *depth as f32 * size * 1.5;
+                let marker = (!marker.is_empty()).then_some((x, marker.as_str()));
+                self.paragraph(x + size * 1.5, size, tokens, marker)
+            }
+            Block::Table(rows) => {
+                self.gap(size);
+                self.table(rows)
+            }
+            Block::Rule => {
+                self.gap(size);
+                self.line(size / 2.0);
+                self.rule();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Lays out `markdown` as SVG text elements and splits it into pages that each fit the
+/// configured box.
+pub fn markdown_pages(
+    markdown: &str,
+    measure: &TextMeasure,
+    options: &MarkdownOptions,
+) -> Result<Vec<String>> {
+    let mut layout = Layout {
+        measure,
+        options,
+        pages: vec![],
+        page: String::new(),
+        y: 0.0,
+    };
+    for block in parse_blocks(markdown) {
+        layout.block(&block)?;
+    }
+    if !layout.page.is_empty() || layout.pages.is_empty() {
+        layout.pages.push(layout.page);
+    }
+    Ok(layout
+        .pages
+        .into_iter()
+        .map(|page| format!(r#"<g class="markdown">{}</g>"#, page))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use resvg::usvg::fontdb::Database;
+    use std::sync::Arc;
+
+    fn word(text: &str, bold: bool, space_before: bool) -> Token {
+        Token::Word {
+            text: text.to_string(),
+            bold,
+            italic: false,
+            space_before,
+        }
+    }
+
+    fn options(height: f32) -> MarkdownOptions {
+        MarkdownOptions {
+            width: 300.0,
+            height,
+            font: Font {
+                family: "Roboto".to_string(),
+                weight: 400,
+                size: 16.0,
+            },
+            line_height: 1.25,
+        }
+    }
+
+    fn text_measure() -> TextMeasure {
+        let mut fontdb = Database::new();
+        fontdb.load_fonts_dir("fonts");
+        TextMeasure::new(Arc::new(fontdb))
+    }
+
+    #[test]
+    fn it_should_parse_headings_and_bold_text() {
+        let blocks = parse_blocks("# On-call\n\nPrimary is **Alex**, secondary is Sam.");
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Heading(HeadingLevel::H1, vec![word("On-call", false, false)]),
+                Block::Paragraph(vec![
+                    word("Primary", false, false),
+                    word("is", false, true),
+                    word("Alex", true, true),
+                    word(",", false, false),
+                    word("secondary", false, true),
+                    word("is", false, true),
+                    word("Sam.", false, true),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_parse_nested_lists() {
+        let blocks = parse_blocks("1. Deploy\n   - canary\n2. Verify");
+        assert_eq!(
+            blocks,
+            vec![
+                Block::ListItem {
+                    marker: "1.".to_string(),
+                    depth: 0,
+                    tokens: vec![word("Deploy", false, false)],
+                },
+                Block::ListItem {
+                    marker: BULLET.to_string(),
+                    depth: 1,
+                    tokens: vec![word("canary", false, false)],
+                },
+                Block::ListItem {
+                    marker: "2.".to_string(),
+                    depth: 0,
+                    tokens: vec![word("Verify", false, false)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_parse_tables() {
+        let blocks = parse_blocks("| Day | Who |\n|---|---|\n| Mon | Alex |\n| Tue | Sam |");
+        assert_eq!(
+            blocks,
+            vec![Block::Table(vec![
+                vec!["Day".to_string(), "Who".to_string()],
+                vec!["Mon".to_string(), "Alex".to_string()],
+                vec!["Tue".to_string(), "Sam".to_string()],
+            ])]
+        );
+    }
+
+    #[test]
+    fn it_should_paginate_overflowing_text() {
+        let markdown = (1..=20)
+            .map(|i| format!("- Item number {} of the weekly notes", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let pages = markdown_pages(&markdown, &text_measure(), &options(100.0)).unwrap();
+        assert!(pages.len() > 1);
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| page.matches(BULLET).count())
+                .sum::<usize>(),
+            20
+        );
+        for page in &pages {
+            for y in page.split(r#" y=""#).skip(1) {
+                let y: f32 = y.split('"').next().unwrap().parse().unwrap();
+                assert!(y <= 100.0);
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_escape_text() {
+        let pages = markdown_pages("a < b & c", &text_measure(), &options(100.0)).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].contains("a &lt; b &amp; c"));
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use resvg::tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
+use resvg::usvg::Tree;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MashupLayout {
+    LeftRight,
+    TopBottom,
+    Quadrants,
+    Grid { columns: u32, rows: u32 },
+}
+
+/// Placement of a region in the layout grid, regions without a `column` and `row` fill the
+/// cells in order.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct MashupCell {
+    pub column: Option<u32>,
+    pub row: Option<u32>,
+    pub column_span: Option<u32>,
+    pub row_span: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl MashupLayout {
+    fn grid(&self) -> (u32, u32) {
+        match self {
+            MashupLayout::LeftRight => (2, 1),
+            MashupLayout::TopBottom => (1, 2),
+            MashupLayout::Quadrants => (2, 2),
+            MashupLayout::Grid { columns, rows } => ((*columns).max(1), (*rows).max(1)),
+        }
+    }
+
+    pub fn regions(&self, cells: &[MashupCell], width: u32, height: u32) -> Vec<Region> {
+        let (columns, rows) = self.grid();
+        cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let index = index as u32;
+                let column = cell.column.unwrap_or(index % columns).min(columns - 1);
+                let row = cell.row.unwrap_or(index / columns).min(rows - 1);
+                let column_span = cell.column_span.unwrap_or(1).clamp(1, columns - column);
+                let row_span = cell.row_span.unwrap_or(1).clamp(1, rows - row);
+                let x = width * column / columns;
+                let y = height * row / rows;
+                Region {
+                    x,
+                    y,
+                    width: width * (column + column_span) / columns - x,
+                    height: height * (row + row_span) / rows - y,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Renders each region's document on its own, so styles, ids and namespaces can't leak between
+/// regions. Documents keep their own coordinate system and are scaled to fit their region, so
+/// both full screen templates and templates sized for the region work.
+pub fn compose(width: u32, height: u32, regions: &[(Region, Tree)]) -> Result<Pixmap> {
+    let mut pixmap = Pixmap::new(width, height).context("invalid mashup size")?;
+    pixmap.fill(Color::WHITE);
+    for (region, tree) in regions {
+        let Some(mut region_pixmap) = Pixmap::new(region.width, region.height) else {
+            continue;
+        };
+        let size = tree.size();
+        let scale = (region.width as f32 / size.width()).min(region.height as f32 / size.height());
+        let transform = Transform::from_scale(scale, scale).post_translate(
+            (region.width as f32 - size.width() * scale) / 2.0,
+            (region.height as f32 - size.height() * scale) / 2.0,
+        );
+        resvg::render(tree, transform, &mut region_pixmap.as_mut());
+        pixmap.draw_pixmap(
+            region.x as i32,
+            region.y as i32,
+            region_pixmap.as_ref(),
+            &PixmapPaint::default(),
+            Transform::identity(),
+            None,
+        );
+    }
+    Ok(pixmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_split_predefined_layouts() {
+        let cells = vec![MashupCell::default(); 4];
+        assert_eq!(
+            MashupLayout::LeftRight.regions(&cells[..2], 800, 480),
+            vec![
+                Region {
+                    x: 0,
+                    y: 0,
+                    width: 400,
+                    height: 480
+                },
+                Region {
+                    x: 400,
+                    y: 0,
+                    width: 400,
+                    height: 480
+                },
+            ]
+        );
+        let quadrants = MashupLayout::Quadrants.regions(&cells, 800, 480);
+        assert_eq!(
+            quadrants[3],
+            Region {
+                x: 400,
+                y: 240,
+                width: 400,
+                height: 240
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_place_spanning_cells_in_custom_grid() {
+        let layout = MashupLayout::Grid {
+            columns: 3,
+            rows: 2,
+        };
+        let cells = vec![
+            MashupCell {
+                column: Some(0),
+                row: Some(0),
+                column_span: Some(2),
+                row_span: Some(2),
+            },
+            MashupCell {
+                column: Some(2),
+                row: Some(0),
+                ..MashupCell::default()
+            },
+            MashupCell {
+                column: Some(2),
+                row: Some(1),
+                column_span: Some(5),
+                ..MashupCell::default()
+            },
+        ];
+        let regions = layout.regions(&cells, 800, 480);
+        assert_eq!(
+            regions,
+            vec![
+                Region {
+                    x: 0,
+                    y: 0,
+                    width: 533,
+                    height: 480
+                },
+                Region {
+                    x: 533,
+                    y: 0,
+                    width: 267,
+                    height: 240
+                },
+                Region {
+                    x: 533,
+                    y: 240,
+                    width: 267,
+                    height: 240
+                },
+            ]
+        );
+    }
+
+    fn tree(svg: &str, region: &Region) -> Tree {
+        let options = resvg::usvg::Options {
+            default_size: resvg::usvg::Size::from_wh(region.width as f32, region.height as f32)
+                .unwrap(),
+            ..resvg::usvg::Options::default()
+        };
+        Tree::from_data(svg.as_bytes(), &options).unwrap()
+    }
+
+    fn is_black(pixmap: &Pixmap, x: u32, y: u32) -> bool {
+        let pixel = pixmap.pixel(x, y).unwrap();
+        (pixel.red(), pixel.green(), pixel.blue()) == (0, 0, 0)
+    }
+
+    #[test]
+    fn it_should_compose_regions_independently() {
+        let left = Region {
+            x: 0,
+            y: 0,
+            width: 400,
+            height: 480,
+        };
+        let right = Region { x: 400, ..left };
+        // A full screen template scaled into the left half, with a style and id that would
+        // restyle the right half if the documents were merged.
+        let styled = r##"<svg width="800" height="480" viewBox="0 0 800 480" xmlns="http://www.w3.org/2000/svg">
+            <style>rect { fill: black; }</style>
+            <linearGradient id="paint"><stop offset="0" stop-color="black"/></linearGradient>
+            <rect width="800" height="480"/>
+        </svg>"##;
+        // An Inkscape document without a size, laid out for the region.
+        let inkscape = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" xmlns:sodipodi="http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd" inkscape:version="1.3">
+            <sodipodi:namedview id="namedview1" pagecolor="#ffffff"/>
+            <linearGradient id="paint"><stop offset="0" stop-color="white"/></linearGradient>
+            <rect inkscape:label="background" width="400" height="480" fill="url(#paint)"/>
+            <rect x="10" y="10" width="20" height="20" fill="#000000"/>
+        </svg>"##;
+        let pixmap = compose(
+            800,
+            480,
+            &[(left, tree(styled, &left)), (right, tree(inkscape, &right))],
+        )
+        .unwrap();
+        // 800x480 scales to 400x240 in the left half, centered vertically.
+        assert!(!is_black(&pixmap, 200, 100));
+        assert!(is_black(&pixmap, 200, 240));
+        assert!(is_black(&pixmap, 5, 125));
+        assert!(!is_black(&pixmap, 200, 370));
+        assert!(is_black(&pixmap, 415, 15));
+        assert!(!is_black(&pixmap, 405, 5));
+        assert!(!is_black(&pixmap, 600, 240));
+        assert!(!is_black(&pixmap, 795, 475));
+    }
+}
@@ -1,10 +1,26 @@
-use anyhow::{Context, Result};
+mod chart;
+mod markdown;
+pub mod mashup;
+mod qr;
+mod raster;
+mod text;
+
+use crate::display::chart::{Chart, ChartOptions, area_chart, bar_chart, sparkline};
+use crate::display::markdown::{MarkdownOptions, markdown_pages};
+use crate::display::mashup::{MashupCell, MashupLayout, compose};
+use crate::display::qr::{parse_ec_level, qr_code};
+use crate::display::raster::{inline_image, is_raster_file, load_dithered, to_rgba};
+use crate::display::text::{Font, TextMeasure};
+use anyhow::{Context, Result, anyhow};
+use minijinja::value::Kwargs;
 use resvg::usvg;
 use resvg::usvg::Transform;
+use resvg::usvg::fontdb::Database;
 use serde_json::{Map, Value};
 use sha2::{Digest, Sha256};
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 use tiny_skia::Pixmap;
 
@@ -17,6 +33,7 @@ const IMAGE_SIZE: usize = PIXEL_BUFFER_SIZE + HEADER_SIZE;
 const WHITE: [u8; 4] = [255, 255, 255, 255];
 const BLANK_BMP: &[u8] = include_bytes!("blank.bmp");
 const TEMPLATE_FILE_EXT: &str = "jinja";
+const SVG_FILE_EXT: &str = "svg";
 
 pub struct Template {
     pub name: String,
@@ -25,15 +42,28 @@ pub struct Template {
 
 pub struct DisplayRenderer {
     templates: Vec<Template>,
-    fonts_path: PathBuf,
+    templates_path: PathBuf,
+    assets_path: PathBuf,
+    fontdb: Arc<Database>,
 }
 
 impl DisplayRenderer {
-    pub fn new(fonts_path: PathBuf, templates_path: PathBuf) -> Result<DisplayRenderer> {
-        let templates = DisplayRenderer::templates(templates_path)?;
+    /// Relative image paths in templates are resolved against `assets_path`, falling back to
+    /// `templates_path` when no assets directory is configured.
+    pub fn new(
+        fonts_path: PathBuf,
+        templates_path: PathBuf,
+        assets_path: Option<PathBuf>,
+    ) -> Result<DisplayRenderer> {
+        let templates = DisplayRenderer::templates(templates_path.clone())?;
+        let assets_path = assets_path.unwrap_or(templates_path.clone());
+        let mut fontdb = Database::new();
+        fontdb.load_fonts_dir(&fonts_path);
         Ok(DisplayRenderer {
             templates,
-            fonts_path,
+            templates_path,
+            assets_path,
+            fontdb: Arc::new(fontdb),
         })
     }
 
@@ -64,16 +94,120 @@ impl DisplayRenderer {
         for Template { name, content, .. } in &self.templates {
             env.add_template(name, content)?;
         }
+        let assets_path = self.assets_path.clone();
+        env.add_function(
+            "inline_image",
+            move |path: String, width: u32, height: u32| {
+                inline_image(&assets_path.join(&path), width, height)
+                    .context(format!("failed to inline image {}", path))
+                    .map_err(template_error)
+            },
+        );
+
+        let text_measure = Arc::new(TextMeasure::new(self.fontdb.clone()));
+        let measure = text_measure.clone();
+        env.add_function(
+            "text_width",
+            move |text: String, font_size: f32, kwargs: Kwargs| {
+                let font = Font::from_kwargs(font_size, &kwargs)?;
+                measure.width(&text, &font).map_err(template_error)
+            },
+        );
+        let measure = text_measure.clone();
+        env.add_function(
+            "wrap_text",
+            move |text: String, width: f32, font_size: f32, kwargs: Kwargs| {
+                let font = Font::from_kwargs(font_size, &kwargs)?;
+                measure.wrap(&text, width, &font).map_err(template_error)
+            },
+        );
+        let measure = text_measure.clone();
+        env.add_function(
+            "truncate_text",
+            move |text: String, width: f32, font_size: f32, kwargs: Kwargs| {
+                let font = Font::from_kwargs(font_size, &kwargs)?;
+                measure
+                    .truncate(&text, width, &font)
+                    .map_err(template_error)
+            },
+        );
+        let measure = text_measure.clone();
+        env.add_function(
+            "fit_text",
+            move |text: String, width: f32, height: f32, max_font_size: f32, kwargs: Kwargs| {
+                let min_font_size = kwargs.get::<Option<f32>>("min_font_size")?.unwrap_or(8.0);
+                let line_height = kwargs.get::<Option<f32>>("line_height")?.unwrap_or(1.2);
+                let font = Font::from_kwargs(max_font_size, &kwargs)?;
+                measure
+                    .fit(&text, width, height, &font, min_font_size, line_height)
+                    .map_err(template_error)
+            },
+        );
+        let measure = text_measure.clone();
+        env.add_function(
+            "markdown",
+            move |text: String, width: f32, height: f32, kwargs: Kwargs| {
+                let font_size = kwargs.get::<Option<f32>>("font_size")?.unwrap_or(16.0);
+                let line_height = kwargs.get::<Option<f32>>("line_height")?.unwrap_or(1.3);
+                let options = MarkdownOptions {
+                    width,
+                    height,
+                    font: Font::from_kwargs(font_size, &kwargs)?,
+                    line_height,
+                };
+                let pages = markdown_pages(&text, &measure, &options).map_err(template_error)?;
+                Ok::<_, minijinja::Error>(
+                    pages
+                        .into_iter()
+                        .map(minijinja::Value::from_safe_string)
+                        .collect::<Vec<_>>(),
+                )
+            },
+        );
+
+        let charts: [(&str, Chart); 3] = [
+            ("sparkline", sparkline),
+            ("bar_chart", bar_chart),
+            ("area_chart", area_chart),
+        ];
+        for (name, chart) in charts {
+            env.add_function(
+                name,
+                move |values: Vec<Option<f64>>, width: f64, height: f64, kwargs: Kwargs| {
+                    let options = ChartOptions::from_kwargs(width, height, &kwargs)?;
+                    Ok::<_, minijinja::Error>(minijinja::Value::from_safe_string(chart(
+                        &values, &options,
+                    )))
+                },
+            );
+        }
+
+        env.add_function("qr_code", |data: String, size: u32, kwargs: Kwargs| {
+            let ec_level = kwargs.get::<Option<String>>("ec_level")?;
+            let quiet_zone = kwargs.get::<Option<usize>>("quiet_zone")?;
+            let ec_level =
+                parse_ec_level(ec_level.as_deref().unwrap_or("M")).map_err(template_error)?;
+            qr_code(&data, size, ec_level, quiet_zone)
+                .map(minijinja::Value::from_safe_string)
+                .map_err(template_error)
+        });
         Ok(env)
     }
 
     fn usvg_opt(&self) -> usvg::Options {
-        let mut opt = usvg::Options::default();
-        opt.fontdb_mut().load_fonts_dir(&self.fonts_path);
-        opt
+        usvg::Options {
+            resources_dir: Some(self.assets_path.clone()),
+            fontdb: self.fontdb.clone(),
+            ..usvg::Options::default()
+        }
     }
 
     pub fn render_jinja(&self, template: &str, ctx: &Map<String, Value>) -> Result<DisplayImage> {
+        let svg = self.render_jinja_svg(template, ctx)?;
+        self.render(&svg)
+    }
+
+    fn render_jinja_svg(&self, template: &str, ctx: &Map<String, Value>) -> Result<String> {
         let env = self.minijinja_env()?;
         let template = env.get_template(template)?;
 
@@ -87,11 +221,79 @@ impl DisplayRenderer {
         let mut ctx = ctx.clone();
         ctx.insert("icons".to_string(), Value::Object(icons_context));
 
-        let svg = template.render(ctx)?;
+        Ok(template.render(ctx)?)
+    }
 
+    pub fn render_file(&self, filename: &str, ctx: &Map<String, Value>) -> Result<DisplayImage> {
+        let path = Path::new(filename);
+        if is_raster_file(path) {
+            return self.render_raster(&self.templates_path.join(path));
+        }
+        let svg = self.render_svg(filename, ctx, WIDTH as u32, HEIGHT as u32)?;
         self.render(&svg)
     }
 
+    /// Renders a playlist file to an SVG document, raster images are dithered to
+    /// `width` by `height` and embedded.
+    fn render_svg(
+        &self,
+        filename: &str,
+        ctx: &Map<String, Value>,
+        width: u32,
+        height: u32,
+    ) -> Result<String> {
+        let path = Path::new(filename);
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(TEMPLATE_FILE_EXT) => self.render_jinja_svg(filename, ctx),
+            Some(SVG_FILE_EXT) => {
+                let path = self.templates_path.join(path);
+                read_to_string(&path).context(format!("failed to read {:?}", path))
+            }
+            _ if is_raster_file(path) => {
+                let image = inline_image(&self.templates_path.join(path), width, height)?;
+                Ok(format!(
+                    r#"<svg width="{width}" height="{height}" xmlns="http://www.w3.org/2000/svg"><image href="{image}" width="{width}" height="{height}"/></svg>"#,
+                ))
+            }
+            _ => Err(anyhow!("unsupported playlist file: {}", filename)),
+        }
+    }
+
+    /// Renders each region's file with its own context into one screen. Templates receive the
+    /// placement of their region as the `region` context.
+    pub fn render_mashup(
+        &self,
+        layout: &MashupLayout,
+        regions: &[(MashupCell, String, Map<String, Value>)],
+    ) -> Result<DisplayImage> {
+        let cells: Vec<MashupCell> = regions.iter().map(|(cell, ..)| cell.clone()).collect();
+        let placements = layout.regions(&cells, WIDTH as u32, HEIGHT as u32);
+        let mut trees = vec![];
+        for (region, (_, filename, ctx)) in placements.into_iter().zip(regions) {
+            let mut ctx = ctx.clone();
+            ctx.insert("region".to_string(), serde_json::to_value(region)?);
+            let svg = self
+                .render_svg(filename, &ctx, region.width, region.height)
+                .context(format!("failed to render mashup region {}", filename))?;
+            // Documents without a size are laid out for their region
+            let options = usvg::Options {
+                default_size: usvg::Size::from_wh(region.width as f32, region.height as f32)
+                    .context(format!("empty mashup region for {}", filename))?,
+                ..self.usvg_opt()
+            };
+            let tree = usvg::Tree::from_data(svg.as_bytes(), &options)
+                .context(format!("failed to parse mashup region {}", filename))?;
+            trees.push((region, tree));
+        }
+        let pixmap = compose(WIDTH as u32, HEIGHT as u32, &trees)?;
+        create_bmp(&pixmap.take())
+    }
+
+    pub fn render_raster(&self, path: &Path) -> Result<DisplayImage> {
+        let image = load_dithered(path, WIDTH as u32, HEIGHT as u32)?;
+        create_bmp(&to_rgba(&image))
+    }
+
     pub fn render(&self, svg: &str) -> Result<DisplayImage> {
         let tree = usvg::Tree::from_data(svg.as_bytes(), &self.usvg_opt())?;
 
@@ -106,6 +308,10 @@ impl DisplayRenderer {
 
 pub type DisplayImage = [u8; IMAGE_SIZE];
 
+fn template_error(e: anyhow::Error) -> minijinja::Error {
+    minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, format!("{:#}", e))
+}
+
 fn create_bmp(pixel_data: &[u8]) -> Result<DisplayImage> {
     let mut buffer: [u8; IMAGE_SIZE] = [0; IMAGE_SIZE];
     let pixels = buffer[HEADER_SIZE..].as_mut();
@@ -138,15 +344,18 @@ pub fn generate_filename(api_key: String, timestamp: SystemTime) -> Result<Strin
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use serde_json::json;
     use std::fs::write;
+    use std::ops::Range;
     use std::path::Path;
     use std::time::Duration;
 
     #[test]
     fn it_should_render_image() {
-        let display_renderer = DisplayRenderer::new("fonts".into(), "templates".into()).unwrap();
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), "templates".into(), None).unwrap();
         let ctx = Map::new();
         let image = display_renderer
             .render_jinja("test.svg.jinja", &ctx)
@@ -154,6 +363,182 @@ mod tests {
         write(Path::new("test.bmp"), image).unwrap();
     }
 
+    #[test]
+    fn it_should_render_setup_template() {
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), "templates".into(), None).unwrap();
+        let mut ctx = Map::new();
+        ctx.insert(
+            "setup".to_string(),
+            serde_json::json!({ "base_url": "http://example.localhost" }),
+        );
+        let image = display_renderer.render_file("setup.svg.jinja", &ctx);
+        assert!(image.is_ok());
+    }
+
+    #[test]
+    fn it_should_render_mashup() {
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), "templates".into(), None).unwrap();
+        let regions = vec![
+            (
+                MashupCell::default(),
+                "test.svg.jinja".to_string(),
+                Map::new(),
+            ),
+            (
+                MashupCell::default(),
+                "test.svg.jinja".to_string(),
+                Map::new(),
+            ),
+        ];
+        let image = display_renderer.render_mashup(&MashupLayout::LeftRight, &regions);
+        let image = image.unwrap();
+        // Each half holds the template scaled to 400x240, centered vertically.
+        assert!(!any_black(&image, 0..400, 0..118));
+        assert!(any_black(&image, 0..400, 122..358));
+        assert!(!any_black(&image, 400..800, 0..118));
+        assert!(any_black(&image, 400..800, 122..358));
+        assert!(!any_black(&image, 0..800, 362..480));
+    }
+
+    /// Whether the pixel at `x`, `y` from the top left is white in a rendered BMP.
+    pub(crate) fn is_white(image: &[u8], x: usize, y: usize) -> bool {
+        let index = (HEIGHT - 1 - y) * WIDTH + x;
+        image[HEADER_SIZE + index / PIXELS_PER_BYTE] & (1 << (7 - index % PIXELS_PER_BYTE)) != 0
+    }
+
+    pub(crate) fn any_black(image: &[u8], columns: Range<usize>, rows: Range<usize>) -> bool {
+        rows.into_iter()
+            .any(|y| columns.clone().any(|x| !is_white(image, x, y)))
+    }
+
+    #[test]
+    fn it_should_render_svg_file() {
+        let templates = tempfile::tempdir().unwrap();
+        write(
+            templates.path().join("floor-map.svg"),
+            r#"<svg width="800" height="480" xmlns="http://www.w3.org/2000/svg">
+                <rect width="800" height="480" fill="white"/>
+                <rect width="400" height="480" fill="black"/>
+            </svg>"#,
+        )
+        .unwrap();
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), templates.path().into(), None).unwrap();
+        let image = display_renderer
+            .render_file("floor-map.svg", &Map::new())
+            .unwrap();
+        assert!(!is_white(&image, 10, 10));
+        assert!(!is_white(&image, 399, 470));
+        assert!(is_white(&image, 401, 10));
+        assert!(is_white(&image, 790, 470));
+    }
+
+    #[test]
+    fn it_should_render_raster_file() {
+        let templates = tempfile::tempdir().unwrap();
+        let png = image::GrayImage::from_fn(400, 240, |x, _| {
+            image::Luma([if x < 200 { 0 } else { 255 }])
+        });
+        png.save(templates.path().join("announcement.png")).unwrap();
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), templates.path().into(), None).unwrap();
+        let image = display_renderer
+            .render_file("announcement.png", &Map::new())
+            .unwrap();
+        // The image is scaled up to the full screen.
+        assert!(!is_white(&image, 10, 10));
+        assert!(!is_white(&image, 390, 470));
+        assert!(is_white(&image, 410, 10));
+        assert!(is_white(&image, 790, 470));
+
+        let svg = display_renderer
+            .render_svg("announcement.png", &Map::new(), 800, 480)
+            .unwrap();
+        let image = display_renderer.render(&svg).unwrap();
+        assert!(!is_white(&image, 10, 240));
+        assert!(is_white(&image, 790, 240));
+    }
+
+    #[test]
+    fn it_should_render_weather_hours_from_context() {
+        let slot = |hour: u32, temperature: f64, precipitation_probability: f64| {
+            json!({
+                "icon": "sunny",
+                "temperature": temperature,
+                "hour": hour,
+                "precipitation_probability": precipitation_probability,
+            })
+        };
+        let ctx = json!({
+            "weather": {
+                "current": slot(14, 21.4, 0.0),
+                "time": "14:05",
+                "units": { "temperature": "°C", "wind_speed": "km/h", "precipitation": "mm" },
+                "days": [
+                    { "title": "Today", "hours": [null, null, null, slot(15, 22.0, 40.0), slot(18, 19.0, 0.0)] },
+                    { "title": "Tomorrow", "hours": [slot(0, 12.0, 0.0), slot(9, 15.0, 0.0)] },
+                ],
+            }
+        });
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), "templates".into(), None).unwrap();
+        let svg = display_renderer
+            .render_jinja_svg("weather.svg.jinja", ctx.as_object().unwrap())
+            .unwrap();
+        assert!(svg.contains("Now: 21°C"));
+        assert!(svg.contains("22°C"));
+        assert!(svg.contains("3pm 40%"));
+        assert!(svg.contains("12am"));
+        assert!(svg.contains("9am"));
+        assert!(!svg.contains("°F"));
+        assert_eq!(svg.matches(r#"class="sm-text""#).count(), 4);
+        assert!(display_renderer.render(&svg).is_ok());
+    }
+
+    #[test]
+    fn it_should_wrap_text_block_with_its_font() {
+        let templates = tempfile::tempdir().unwrap();
+        std::fs::copy(
+            "templates/components.svg.jinja",
+            templates.path().join("components.svg.jinja"),
+        )
+        .unwrap();
+        write(
+            templates.path().join("block.svg.jinja"),
+            r#"{%- import 'components.svg.jinja' as components -%}
+            <svg width="800" height="480" xmlns="http://www.w3.org/2000/svg">
+                {{ components.text_block("Quarterly planning review", 300, 40, font_family="Junction", font_weight=700) }}
+            </svg>"#,
+        )
+        .unwrap();
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), templates.path().into(), None).unwrap();
+        let svg = display_renderer
+            .render_jinja_svg("block.svg.jinja", &Map::new())
+            .unwrap();
+        assert!(svg.contains(r#"font-family="Junction" font-weight="700""#));
+        let font = Font {
+            family: "Junction".to_string(),
+            weight: 700,
+            size: 40.0,
+        };
+        let lines = TextMeasure::new(display_renderer.fontdb.clone())
+            .wrap("Quarterly planning review", 300.0, &font)
+            .unwrap();
+        assert_eq!(svg.matches("<tspan").count(), lines.len());
+        assert!(display_renderer.render(&svg).is_ok());
+    }
+
+    #[test]
+    fn it_should_reject_unsupported_file() {
+        let display_renderer =
+            DisplayRenderer::new("fonts".into(), "templates".into(), None).unwrap();
+        let result = display_renderer.render_file("default.json", &Map::new());
+        assert!(result.is_err());
+    }
+
     #[test]
     fn it_should_generate_filename() {
         let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1234567890);
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result, anyhow};
+use qrcode::{Color, EcLevel, QrCode};
+use std::fmt::Write;
+
+const DEFAULT_QUIET_ZONE: usize = 4;
+
+pub fn parse_ec_level(ec_level: &str) -> Result<EcLevel> {
+    match ec_level.to_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        _ => Err(anyhow!("invalid error correction level: {}", ec_level)),
+    }
+}
+
+/// Renders `data` as an SVG fragment of `size` by `size` pixels. Modules are scaled by a whole
+/// number of pixels and centered so their edges stay sharp on the display.
+pub fn qr_code(
+    data: &str,
+    size: u32,
+    ec_level: EcLevel,
+    quiet_zone: Option<usize>,
+) -> Result<String> {
+    let code = QrCode::with_error_correction_level(data, ec_level)
+        .context(format!("failed to encode QR code for {}", data))?;
+    let quiet_zone = quiet_zone.unwrap_or(DEFAULT_QUIET_ZONE);
+    let width = code.width();
+    let modules = width + quiet_zone * 2;
+    let module_size = (size as usize / modules).max(1);
+    let offset = (size as isize - (module_size * modules) as isize) / 2;
+
+    let mut d = String::new();
+    for (y, row) in code.to_colors().chunks(width).enumerate() {
+        let mut x = 0;
+        while x < width {
+            if row[x] == Color::Light {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < width && row[x] == Color::Dark {
+                x += 1;
+            }
+            let _ = write!(
+                d,
+                "M{} {}h{}v1h-{}z",
+                start + quiet_zone,
+                y + quiet_zone,
+                x - start,
+                x - start
+            );
+        }
+    }
+
+    Ok(format!(
+        r#"<svg x="0" y="0" width="{size}" height="{size}" class="qr-code"><rect width="{size}" height="{size}" fill="white"/><g transform="translate({offset} {offset}) scale({module_size})"><path d="{d}" fill="black" shape-rendering="crispEdges"/></g></svg>"#,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_render_qr_code_with_whole_pixel_modules() {
+        let markup = qr_code("https://example.localhost", 200, EcLevel::M, None).unwrap();
+        // Version 2 codes are 25 modules wide, 33 with the quiet zone.
+        assert!(markup.contains("scale(6)"));
+        assert!(markup.contains("translate(1 1)"));
+        assert!(markup.contains(r#"width="200" height="200""#));
+    }
+
+    #[test]
+    fn it_should_merge_adjacent_dark_modules() {
+        let markup = qr_code("WIFI:T:WPA;S:office;P:secret;;", 100, EcLevel::L, Some(0)).unwrap();
+        // The top row starts with a 7 module wide finder pattern.
+        assert!(markup.contains("M0 0h7v1h-7z"));
+    }
+
+    #[test]
+    fn it_should_parse_ec_level() {
+        assert_eq!(parse_ec_level("h").unwrap(), EcLevel::H);
+        assert!(parse_ec_level("X").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use image::imageops::colorops::BiLevel;
+use image::imageops::{FilterType, dither, overlay};
+use image::{GrayImage, ImageFormat, Luma};
+use std::io::Cursor;
+use std::path::Path;
+
+pub const RASTER_FILE_EXTS: [&str; 4] = ["png", "jpg", "jpeg", "bmp"];
+
+pub fn is_raster_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| RASTER_FILE_EXTS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub fn load_dithered(path: &Path, width: u32, height: u32) -> Result<GrayImage> {
+    let image = image::open(path).context(format!("failed to open image {:?}", path))?;
+    let image = image.resize(width, height, FilterType::Lanczos3).to_luma8();
+
+    let mut canvas = GrayImage::from_pixel(width, height, Luma([255]));
+    let x = (width - image.width()) / 2;
+    let y = (height - image.height()) / 2;
+    overlay(&mut canvas, &image, x.into(), y.into());
+
+    dither(&mut canvas, &BiLevel);
+    Ok(canvas)
+}
+
+pub fn inline_image(path: &Path, width: u32, height: u32) -> Result<String> {
+    let image = load_dithered(path, width, height)?;
+    let mut png = Cursor::new(vec![]);
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .context(format!("failed to encode image {:?}", path))?;
+    Ok(format!(
+        "data:image/png;base64,{}",
+        BASE64_STANDARD.encode(png.into_inner())
+    ))
+}
+
+pub fn to_rgba(image: &GrayImage) -> Vec<u8> {
+    image
+        .pixels()
+        .flat_map(|Luma([luma])| [*luma, *luma, *luma, 255])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::Builder;
+
+    #[test]
+    fn it_should_fit_and_dither_image() {
+        let file = Builder::new().suffix(".png").tempfile().unwrap();
+        let gradient = GrayImage::from_fn(200, 100, |x, _| Luma([(x * 255 / 199) as u8]));
+        gradient.save(file.path()).unwrap();
+
+        let image = load_dithered(file.path(), 100, 100).unwrap();
+        assert_eq!(image.dimensions(), (100, 100));
+        assert!(
+            image
+                .pixels()
+                .all(|Luma([luma])| *luma == 0 || *luma == 255)
+        );
+        assert_eq!(image.get_pixel(50, 0), &Luma([255]));
+        assert!(image.pixels().any(|Luma([luma])| *luma == 0));
+    }
+
+    #[test]
+    fn it_should_inline_image_as_png_data_uri() {
+        let file = Builder::new().suffix(".jpg").tempfile().unwrap();
+        GrayImage::from_pixel(10, 10, Luma([0]))
+            .save(file.path())
+            .unwrap();
+
+        let data_uri = inline_image(file.path(), 20, 20).unwrap();
+        let png = data_uri.strip_prefix("data:image/png;base64,").unwrap();
+        let png = image::load_from_memory(&BASE64_STANDARD.decode(png).unwrap()).unwrap();
+        assert_eq!((png.width(), png.height()), (20, 20));
+    }
+
+    #[test]
+    fn it_should_detect_raster_files() {
+        assert!(is_raster_file(Path::new("floor-map.PNG")));
+        assert!(is_raster_file(Path::new("announcement.jpeg")));
+        assert!(!is_raster_file(Path::new("weather.svg.jinja")));
+    }
+}
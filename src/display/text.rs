@@ -0,0 +1,282 @@
+use anyhow::{Context, Result, anyhow};
+use minijinja::value::Kwargs;
+use resvg::usvg::fontdb::{Database, Family, Query, Weight};
+use std::sync::Arc;
+use ttf_parser::{Face, GlyphId};
+
+const ELLIPSIS: &str = "…";
+const DEFAULT_FONT_FAMILY: &str = "Roboto";
+const DEFAULT_FONT_WEIGHT: u16 = 400;
+const FIT_PRECISION: f32 = 0.25;
+
+pub struct Font {
+    pub family: String,
+    pub weight: u16,
+    pub size: f32,
+}
+
+impl Font {
+    /// Reads the optional `font_family` and `font_weight` keyword arguments of a template
+    /// function, other keyword arguments are left to the caller.
+    pub fn from_kwargs(size: f32, kwargs: &Kwargs) -> Result<Font, minijinja::Error> {
+        Ok(Font {
+            family: kwargs
+                .get::<Option<String>>("font_family")?
+                .unwrap_or(DEFAULT_FONT_FAMILY.to_string()),
+            weight: kwargs
+                .get::<Option<u16>>("font_weight")?
+                .unwrap_or(DEFAULT_FONT_WEIGHT),
+            size,
+        })
+    }
+}
+
+pub struct TextMeasure {
+    fontdb: Arc<Database>,
+}
+
+impl TextMeasure {
+    pub fn new(fontdb: Arc<Database>) -> TextMeasure {
+        TextMeasure { fontdb }
+    }
+
+    fn with_advances<T>(
+        &self,
+        font: &Font,
+        f: impl FnOnce(&dyn Fn(&str, f32) -> f32) -> T,
+    ) -> Result<T> {
+        let id = self
+            .fontdb
+            .query(&Query {
+                families: &[Family::Name(&font.family)],
+                weight: Weight(font.weight),
+                ..Query::default()
+            })
+            .context(format!("no font found for family {}", font.family))?;
+        self.fontdb
+            .with_face_data(id, |data, index| {
+                let face = Face::parse(data, index)
+                    .map_err(|e| anyhow!("failed to parse font {}: {}", font.family, e))?;
+                let units_per_em = face.units_per_em() as f32;
+                let measure = |text: &str, size: f32| {
+                    let units: f32 = text
+                        .chars()
+                        .map(|c| {
+                            let glyph = face.glyph_index(c).unwrap_or(GlyphId(0));
+                            face.glyph_hor_advance(glyph).unwrap_or(0) as f32
+                        })
+                        .sum();
+                    units * size / units_per_em
+                };
+                Ok(f(&measure))
+            })
+            .context(format!("failed to load font data for {}", font.family))?
+    }
+
+    pub fn width(&self, text: &str, font: &Font) -> Result<f32> {
+        self.with_advances(font, |measure| measure(text, font.size))
+    }
+
+    pub fn wrap(&self, text: &str, width: f32, font: &Font) -> Result<Vec<String>> {
+        self.with_advances(font, |measure| {
+            wrap_lines(text, width, &|line: &str| measure(line, font.size))
+        })
+    }
+
+    pub fn truncate(&self, text: &str, width: f32, font: &Font) -> Result<String> {
+        self.with_advances(font, |measure| {
+            if measure(text, font.size) <= width {
+                return text.to_string();
+            }
+            let mut truncated = text.to_string();
+            while !truncated.is_empty() {
+                truncated.pop();
+                let candidate = format!("{}{}", truncated.trim_end(), ELLIPSIS);
+                if measure(&candidate, font.size) <= width {
+                    return candidate;
+                }
+            }
+            ELLIPSIS.to_string()
+        })
+    }
+
+    /// Finds the largest font size between `min_size` and `font.size` at which the wrapped text
+    /// fits inside the box, never larger than `font.size`.
+    pub fn fit(
+        &self,
+        text: &str,
+        width: f32,
+        height: f32,
+        font: &Font,
+        min_size: f32,
+        line_height: f32,
+    ) -> Result<f32> {
+        self.with_advances(font, |measure| {
+            let fits = |size: f32| {
+                let lines = wrap_lines(text, width, &|line: &str| measure(line, size));
+                let fits_width = lines.iter().all(|line| measure(line, size) <= width);
+                let fits_height = lines.len() as f32 * size * line_height <= height;
+                fits_width && fits_height
+            };
+            if fits(font.size) {
+                return font.size;
+            }
+            // `low` is the smallest allowed size or fits, `high` never fits
+            let (mut low, mut high) = (min_size.min(font.size), font.size);
+            while high - low > FIT_PRECISION {
+                let size = (low + high) / 2.0;
+                if fits(size) {
+                    low = size;
+                } else {
+                    high = size;
+                }
+            }
+            low
+        })
+    }
+}
+
+fn wrap_lines(text: &str, width: f32, measure: &dyn Fn(&str) -> f32) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if measure(&candidate) <= width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if line.chars().count() > 1 && measure(&line) > width {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_measure() -> TextMeasure {
+        let mut fontdb = Database::new();
+        fontdb.load_fonts_dir("fonts");
+        TextMeasure::new(Arc::new(fontdb))
+    }
+
+    fn roboto(size: f32) -> Font {
+        Font {
+            family: "Roboto".to_string(),
+            weight: 400,
+            size,
+        }
+    }
+
+    #[test]
+    fn it_should_wrap_text_within_width() {
+        let text_measure = text_measure();
+        let font = roboto(20.0);
+        let text = "Quarterly planning review with the infrastructure and platform teams";
+        let lines = text_measure.wrap(text, 200.0, &font).unwrap();
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(text_measure.width(line, &font).unwrap() <= 200.0);
+        }
+        assert_eq!(lines.join(" "), text);
+    }
+
+    #[test]
+    fn it_should_break_words_longer_than_width() {
+        let lines = wrap_lines("abcdefgh", 3.0, &|line: &str| line.len() as f32);
+        assert_eq!(lines, vec!["abc", "def", "gh"]);
+    }
+
+    #[test]
+    fn it_should_keep_explicit_line_breaks() {
+        let lines = wrap_lines("a b\n\nc", 100.0, &|line: &str| line.len() as f32);
+        assert_eq!(lines, vec!["a b", "", "c"]);
+    }
+
+    #[test]
+    fn it_should_truncate_with_ellipsis() {
+        let text_measure = text_measure();
+        let font = roboto(20.0);
+        let truncated = text_measure
+            .truncate("Quarterly planning review", 100.0, &font)
+            .unwrap();
+        assert!(truncated.ends_with(ELLIPSIS));
+        assert!(text_measure.width(&truncated, &font).unwrap() <= 100.0);
+        assert_eq!(text_measure.truncate("Sync", 100.0, &font).unwrap(), "Sync");
+    }
+
+    #[test]
+    fn it_should_fit_font_size_to_box() {
+        let text_measure = text_measure();
+        let font = roboto(80.0);
+        let short = text_measure
+            .fit("Hi", 200.0, 100.0, &font, 8.0, 1.2)
+            .unwrap();
+        let long = text_measure
+            .fit(
+                "A much longer headline that needs to shrink",
+                200.0,
+                100.0,
+                &font,
+                8.0,
+                1.2,
+            )
+            .unwrap();
+        assert!(long < short);
+        assert!(long >= 8.0);
+        let lines = text_measure
+            .wrap(
+                "A much longer headline that needs to shrink",
+                200.0,
+                &roboto(long),
+            )
+            .unwrap();
+        assert!(lines.len() as f32 * long * 1.2 <= 100.0);
+    }
+
+    #[test]
+    fn it_should_not_fit_above_max_font_size() {
+        let text_measure = text_measure();
+        let size = text_measure
+            .fit(
+                "A long sentence in a tiny box",
+                10.0,
+                10.0,
+                &roboto(6.0),
+                8.0,
+                1.2,
+            )
+            .unwrap();
+        assert_eq!(size, 6.0);
+        let size = text_measure
+            .fit("Hi", 200.0, 100.0, &roboto(6.0), 8.0, 1.2)
+            .unwrap();
+        assert_eq!(size, 6.0);
+    }
+
+    #[test]
+    fn it_should_error_on_unknown_font_family() {
+        let font = Font {
+            family: "Unknown".to_string(),
+            weight: 400,
+            size: 12.0,
+        };
+        assert!(text_measure().width("text", &font).is_err());
+    }
+}